@@ -1,5 +1,5 @@
 use advent_of_code::{
-    grid::{Grid, ADJACENT, NORTHEAST, NORTHWEST, SOUTHEAST, SOUTHWEST},
+    grid::{Grid, GridPoint, GridPointDelta, ADJACENT},
     parse::{parsers, Parser},
 };
 
@@ -11,48 +11,171 @@ fn parse(input: &str) -> Grid<char> {
         .expect("Failed to parse input")
 }
 
+// A rectangle of characters to find in the grid, where `None` matches any character
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stencil {
+    cells: Vec<Vec<Option<char>>>,
+}
+
+impl Stencil {
+    // At least one row, all of the same non-zero length, with `wildcard` standing for any
+    // character
+    pub fn new(rows: &[&str], wildcard: char) -> Self {
+        let cells: Vec<Vec<Option<char>>> = rows
+            .iter()
+            .map(|row| row.chars().map(|c| (c != wildcard).then_some(c)).collect())
+            .collect();
+        assert!(
+            cells.first().is_some_and(|row| !row.is_empty()),
+            "Stencil has no cells"
+        );
+        assert!(
+            cells.windows(2).all(|pair| pair[0].len() == pair[1].len()),
+            "Stencil rows differ in length"
+        );
+        Stencil { cells }
+    }
+
+    fn rotate_clockwise(&self) -> Self {
+        let rows = self.cells.len();
+        let cols = self.cells.first().map_or(0, Vec::len);
+        Stencil {
+            cells: (0..cols)
+                .map(|col| (0..rows).rev().map(|row| self.cells[row][col]).collect())
+                .collect(),
+        }
+    }
+
+    fn reflect(&self) -> Self {
+        Stencil {
+            cells: self
+                .cells
+                .iter()
+                .map(|row| row.iter().rev().copied().collect())
+                .collect(),
+        }
+    }
+
+    // Each distinct way of turning the stencil, mirrored or not, leaving out the ones its own
+    // symmetry makes identical to an earlier one so a match isn't reported twice
+    fn orientations(&self) -> Vec<(Orientation, Stencil)> {
+        let mut orientations: Vec<(Orientation, Stencil)> = Vec::new();
+        for reflected in [false, true] {
+            let mut stencil = if reflected {
+                self.reflect()
+            } else {
+                self.clone()
+            };
+            for quarter_turns in 0..4 {
+                if orientations.iter().all(|(_, seen)| *seen != stencil) {
+                    let orientation = Orientation::Turned {
+                        quarter_turns,
+                        reflected,
+                    };
+                    orientations.push((orientation, stencil.clone()));
+                }
+                stencil = stencil.rotate_clockwise();
+            }
+        }
+        orientations
+    }
+
+    fn matches_at(&self, grid: &Grid<char>, top_left: GridPoint<usize>) -> bool {
+        self.cells.iter().enumerate().all(|(row, cells)| {
+            cells.iter().enumerate().all(|(col, cell)| {
+                grid.get(GridPoint::new(top_left.row + row, top_left.col + col))
+                    .is_ok_and(|c| cell.is_none_or(|cell| *c == cell))
+            })
+        })
+    }
+}
+
+pub enum Pattern {
+    // read in a straight line in any of the eight directions
+    Word(String),
+    Stencil(Stencil),
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Orientation {
+    // the direction a word is read in from its first letter
+    Line(GridPointDelta<isize>),
+    // how many times a stencil is turned clockwise, after being mirrored left to right if
+    // reflected
+    Turned { quarter_turns: u8, reflected: bool },
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Match {
+    // the index of the pattern that matched
+    pub pattern: usize,
+    // the first letter of a word, or the top left corner of a turned stencil
+    pub position: GridPoint<usize>,
+    pub orientation: Orientation,
+}
+
+// Every place each pattern appears, pattern by pattern and then in reading order. A word that
+// reads the same backwards is found once from each end, except a single letter which has no
+// direction and is found once, in the first one. An empty word is found nowhere.
+pub fn search(grid: &Grid<char>, patterns: &[Pattern]) -> Vec<Match> {
+    let mut matches = Vec::new();
+    for (index, pattern) in patterns.iter().enumerate() {
+        match pattern {
+            Pattern::Word(word) => {
+                let length = word.chars().count();
+                let directions = match length {
+                    0 => &ADJACENT[..0],
+                    1 => &ADJACENT[..1],
+                    _ => &ADJACENT[..],
+                };
+                for position in grid.iter_points() {
+                    for &direction in directions {
+                        let found = position
+                            .traverse_by(direction, grid.dimensions())
+                            .take(length)
+                            .filter_map(|p| grid.get(p).ok())
+                            .copied()
+                            .eq(word.chars());
+                        if found {
+                            matches.push(Match {
+                                pattern: index,
+                                position,
+                                orientation: Orientation::Line(direction),
+                            });
+                        }
+                    }
+                }
+            }
+            Pattern::Stencil(stencil) => {
+                let orientations = stencil.orientations();
+                for position in grid.iter_points() {
+                    for (orientation, turned) in &orientations {
+                        if turned.matches_at(grid, position) {
+                            matches.push(Match {
+                                pattern: index,
+                                position,
+                                orientation: *orientation,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+    matches
+}
+
 #[allow(dead_code)]
 pub fn part1(input: &str) -> usize {
-    let grid = parse(input);
-    grid.iter_points()
-        .map(|point| {
-            ADJACENT
-                .into_iter()
-                .filter(|direction| {
-                    point
-                        .traverse_by(*direction, grid.dimensions())
-                        .take(4)
-                        .filter_map(|p| grid.get(p).ok())
-                        .collect::<String>()
-                        == "XMAS"
-                })
-                .count()
-        })
-        .sum()
+    search(&parse(input), &[Pattern::Word("XMAS".to_string())]).len()
 }
 
 #[allow(dead_code)]
 pub fn part2(input: &str) -> usize {
-    let grid = parse(input);
-    grid.iter_points()
-        .filter(|point| {
-            let myself = grid.get(*point).ok().unwrap();
-            let corners: String = [
-                point.add_checked(NORTHWEST, &grid.dimensions()),
-                point.add_checked(NORTHEAST, &grid.dimensions()),
-                point.add_checked(SOUTHEAST, &grid.dimensions()),
-                point.add_checked(SOUTHWEST, &grid.dimensions()),
-            ]
-            .into_iter()
-            .filter_map(|p| p.and_then(|p| grid.get(p).ok()))
-            .collect();
-            *myself == 'A'
-                && (corners == "MMSS"
-                    || corners == "SMMS"
-                    || corners == "SSMM"
-                    || corners == "MSSM")
-        })
-        .count()
+    let x_mas = Stencil::new(&["M.S", ".A.", "M.S"], '.');
+    search(&parse(input), &[Pattern::Stencil(x_mas)]).len()
 }
 
 #[allow(dead_code)]
@@ -86,6 +209,130 @@ MXMXAXMASX
         assert_eq!(part2(EXAMPLE), 9);
     }
 
+    #[test]
+    fn search_words() {
+        let grid = parse("ABC\nDEF\nGHI\n");
+        let patterns = [
+            Pattern::Word("AEI".to_string()),
+            Pattern::Word("FED".to_string()),
+            Pattern::Word("EE".to_string()),
+        ];
+        assert_eq!(
+            search(&grid, &patterns),
+            vec![
+                Match {
+                    pattern: 0,
+                    position: GridPoint::new(0, 0),
+                    orientation: Orientation::Line(GridPointDelta::new(1, 1)),
+                },
+                Match {
+                    pattern: 1,
+                    position: GridPoint::new(1, 2),
+                    orientation: Orientation::Line(GridPointDelta::new(0, -1)),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn search_short_words() {
+        let grid = parse("AB\nBA\n");
+        let patterns = [Pattern::Word(String::new()), Pattern::Word("B".to_string())];
+        assert_eq!(
+            search(&grid, &patterns),
+            vec![
+                Match {
+                    pattern: 1,
+                    position: GridPoint::new(0, 1),
+                    orientation: Orientation::Line(ADJACENT[0]),
+                },
+                Match {
+                    pattern: 1,
+                    position: GridPoint::new(1, 0),
+                    orientation: Orientation::Line(ADJACENT[0]),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Stencil has no cells")]
+    fn stencil_without_rows() {
+        Stencil::new(&[], '.');
+    }
+
+    #[test]
+    #[should_panic(expected = "Stencil has no cells")]
+    fn stencil_with_empty_rows() {
+        Stencil::new(&["", ""], '.');
+    }
+
+    #[test]
+    fn search_stencils() {
+        let grid = parse("ABC\nDEF\nGHI\n");
+        let patterns = [
+            Pattern::Stencil(Stencil::new(&["AB", "D?"], '?')),
+            // only matches "BC" over "EF" once mirrored
+            Pattern::Stencil(Stencil::new(&["CB", "?E"], '?')),
+            // the grid turned a quarter clockwise
+            Pattern::Stencil(Stencil::new(&["GDA", "HEB", "IFC"], '?')),
+        ];
+        assert_eq!(
+            search(&grid, &patterns),
+            vec![
+                Match {
+                    pattern: 0,
+                    position: GridPoint::new(0, 0),
+                    orientation: Orientation::Turned {
+                        quarter_turns: 0,
+                        reflected: false,
+                    },
+                },
+                Match {
+                    pattern: 1,
+                    position: GridPoint::new(0, 1),
+                    orientation: Orientation::Turned {
+                        quarter_turns: 0,
+                        reflected: true,
+                    },
+                },
+                Match {
+                    pattern: 2,
+                    position: GridPoint::new(0, 0),
+                    orientation: Orientation::Turned {
+                        quarter_turns: 3,
+                        reflected: false,
+                    },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn symmetric_stencils() {
+        let orientations = |rows: &[&str]| Stencil::new(rows, '.').orientations().len();
+        assert_eq!(orientations(&["M.S", ".A.", "M.S"]), 4);
+        assert_eq!(orientations(&["A"]), 1);
+        assert_eq!(orientations(&["AB"]), 4);
+        assert_eq!(orientations(&["AA", "AA"]), 1);
+        assert_eq!(orientations(&["AB", "C."]), 8);
+        // wildcards count towards the shape
+        assert_eq!(orientations(&["A.", ".."]), 4);
+    }
+
+    #[test]
+    fn search_everything_at_once() {
+        let x_mas = Stencil::new(&["M.S", ".A.", "M.S"], '.');
+        let matches = search(
+            &parse(EXAMPLE),
+            &[Pattern::Word("XMAS".to_string()), Pattern::Stencil(x_mas)],
+        );
+        assert_eq!(matches.len(), 18 + 9);
+        assert!(matches[..18].iter().all(|found| found.pattern == 0));
+        // the first X-MAS in reading order has its top left corner at row 0, column 1
+        assert_eq!(matches[18].position, GridPoint::new(0, 1));
+    }
+
     #[test]
     fn part1_test() {
         assert_eq!(
@@ -1,8 +1,5 @@
 use advent_of_code::parse::{parsers, Parser};
-use std::{
-    cmp::Ordering,
-    collections::{HashMap, HashSet},
-};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 fn parse(
     input: &str,
@@ -26,24 +23,248 @@ fn parse(
         .expect("Failed to parse input")
 }
 
+// The rules for which pages have to be printed before which others
+pub struct PageOrdering {
+    // every page that has to come after the key
+    rules: HashMap<u32, HashSet<u32>>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum Repair {
+    // the only order of the update's pages that follows the rules between them
+    Unique(Vec<u32>),
+    // an order that follows the rules, and the first two pages found that no rule puts in order
+    Ambiguous {
+        order: Vec<u32>,
+        unordered: (u32, u32),
+    },
+    // pages that each have to come before the next, and the last before the first
+    Cyclic(Vec<u32>),
+}
+
+impl Repair {
+    pub fn order(&self) -> Option<&[u32]> {
+        match self {
+            Repair::Unique(order) | Repair::Ambiguous { order, .. } => Some(order),
+            Repair::Cyclic(_) => None,
+        }
+    }
+}
+
+impl PageOrdering {
+    pub fn new(rules: HashMap<u32, HashSet<u32>>) -> Self {
+        PageOrdering { rules }
+    }
+
+    fn must_precede(&self, before: u32, after: u32) -> bool {
+        self.rules
+            .get(&before)
+            .is_some_and(|later| later.contains(&after))
+    }
+
+    pub fn is_valid(&self, pages: &[u32]) -> bool {
+        pages.iter().enumerate().all(|(idx, &later)| {
+            pages[..idx]
+                .iter()
+                .all(|&earlier| !self.must_precede(later, earlier))
+        })
+    }
+
+    // The broken rules as (before, after) pairs, in the order their first page appears in the
+    // update
+    #[allow(dead_code)]
+    pub fn violations(&self, pages: &[u32]) -> Vec<(u32, u32)> {
+        pages
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, &later)| {
+                pages[..idx]
+                    .iter()
+                    .filter(move |&&earlier| self.must_precede(later, earlier))
+                    .map(move |&earlier| (later, earlier))
+            })
+            .collect()
+    }
+
+    // Sorts the update topologically using only the rules between its own pages. Whenever more
+    // than one page could come next, the one earliest in the update goes first.
+    pub fn repair(&self, pages: &[u32]) -> Repair {
+        // the positions of the pages that have to come before each page
+        let predecessors: Vec<Vec<usize>> = pages
+            .iter()
+            .map(|&page| {
+                (0..pages.len())
+                    .filter(|&other| self.must_precede(pages[other], page))
+                    .collect()
+            })
+            .collect();
+        let mut waiting: Vec<usize> = predecessors.iter().map(Vec::len).collect();
+        let mut placed = vec![false; pages.len()];
+        let mut order = Vec::with_capacity(pages.len());
+        let mut unordered = None;
+        while order.len() < pages.len() {
+            let (next, other) = {
+                let mut ready = (0..pages.len()).filter(|&idx| !placed[idx] && waiting[idx] == 0);
+                (ready.next(), ready.next())
+            };
+            let Some(next) = next else {
+                return Repair::Cyclic(Self::cycle(pages, &predecessors, &placed));
+            };
+            if let Some(other) = other {
+                unordered.get_or_insert((pages[next], pages[other]));
+            }
+            placed[next] = true;
+            order.push(pages[next]);
+            for (idx, before) in predecessors.iter().enumerate() {
+                if before.contains(&next) {
+                    waiting[idx] -= 1;
+                }
+            }
+        }
+        match unordered {
+            None => Repair::Unique(order),
+            Some(unordered) => Repair::Ambiguous { order, unordered },
+        }
+    }
+
+    // Every page left unplaced still waits on another unplaced page, so following those back
+    // from any of them has to come round in a loop
+    fn cycle(pages: &[u32], predecessors: &[Vec<usize>], placed: &[bool]) -> Vec<u32> {
+        let mut path = vec![placed.iter().position(|placed| !placed).unwrap()];
+        loop {
+            let current = *path.last().unwrap();
+            let previous = predecessors[current]
+                .iter()
+                .copied()
+                .find(|&idx| !placed[idx])
+                .unwrap();
+            if let Some(start) = path.iter().position(|&idx| idx == previous) {
+                let mut cycle = path.split_off(start);
+                cycle.reverse();
+                // start from the page that appears first in the update
+                let first = (0..cycle.len()).min_by_key(|&idx| cycle[idx]).unwrap();
+                cycle.rotate_left(first);
+                return cycle.into_iter().map(|idx| pages[idx]).collect();
+            }
+            path.push(previous);
+        }
+    }
+
+    // Every page mentioned in a rule, in ascending order
+    fn pages(&self) -> Vec<u32> {
+        let mut pages: Vec<u32> = self
+            .rules
+            .iter()
+            .flat_map(|(before, after)| after.iter().copied().chain([*before]))
+            .collect::<HashSet<u32>>()
+            .into_iter()
+            .collect();
+        pages.sort_unstable();
+        pages
+    }
+
+    // The pages some chain of rules puts after `start`, optionally ignoring one rule
+    fn reachable(&self, start: u32, ignoring: Option<(u32, u32)>) -> HashSet<u32> {
+        let mut reached = HashSet::new();
+        let mut to_explore = VecDeque::from([start]);
+        while let Some(current) = to_explore.pop_front() {
+            for &next in self.rules.get(&current).into_iter().flatten() {
+                if ignoring != Some((current, next)) && reached.insert(next) {
+                    to_explore.push_back(next);
+                }
+            }
+        }
+        reached
+    }
+
+    // Groups of pages whose rules put every one of them both before and after the others, so no
+    // update containing a whole group can be ordered. Each group is sorted, as are the groups.
+    pub fn cycles(&self) -> Vec<Vec<u32>> {
+        let pages = self.pages();
+        let reach: HashMap<u32, HashSet<u32>> = pages
+            .iter()
+            .map(|&page| (page, self.reachable(page, None)))
+            .collect();
+        let mut grouped = HashSet::new();
+        let mut cycles = Vec::new();
+        for &page in &pages {
+            if grouped.contains(&page) || !reach[&page].contains(&page) {
+                continue;
+            }
+            let group: Vec<u32> = pages
+                .iter()
+                .copied()
+                .filter(|other| reach[&page].contains(other) && reach[other].contains(&page))
+                .collect();
+            grouped.extend(group.iter().copied());
+            cycles.push(group);
+        }
+        cycles
+    }
+
+    // Rules that other rules already imply, in ascending order. Any one of them could be dropped
+    // without changing which pages have to come before which, though dropping several at once
+    // might, since within a cycle they can imply each other.
+    pub fn redundant_rules(&self) -> Vec<(u32, u32)> {
+        let mut redundant: Vec<(u32, u32)> = self
+            .rules
+            .iter()
+            .flat_map(|(&before, after)| after.iter().map(move |&after| (before, after)))
+            .filter(|&(before, after)| {
+                self.reachable(before, Some((before, after)))
+                    .contains(&after)
+            })
+            .collect();
+        redundant.sort_unstable();
+        redundant
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Eq)]
+pub struct RuleReport {
+    pub cycles: Vec<Vec<u32>>,
+    pub redundant: Vec<(u32, u32)>,
+    // pages in the updates that no rule mentions, in ascending order
+    pub unconstrained: Vec<u32>,
+    // whether each update has exactly one order that follows the rules between its pages
+    pub unique_orders: Vec<bool>,
+}
+
+#[allow(dead_code)]
+pub fn rule_report(input: &str) -> RuleReport {
+    let (rules, candidates) = parse(input);
+    let ordering = PageOrdering::new(rules);
+    let updates: Vec<Vec<u32>> = candidates.map(|p| p.collect()).collect();
+    let constrained: HashSet<u32> = ordering.pages().into_iter().collect();
+    let mut unconstrained: Vec<u32> = updates
+        .iter()
+        .flatten()
+        .copied()
+        .filter(|page| !constrained.contains(page))
+        .collect::<HashSet<u32>>()
+        .into_iter()
+        .collect();
+    unconstrained.sort_unstable();
+    RuleReport {
+        cycles: ordering.cycles(),
+        redundant: ordering.redundant_rules(),
+        unconstrained,
+        unique_orders: updates
+            .iter()
+            .map(|pages| matches!(ordering.repair(pages), Repair::Unique(_)))
+            .collect(),
+    }
+}
+
 #[allow(dead_code)]
 pub fn part1(input: &str) -> u32 {
     let (rules, candidates) = parse(input);
+    let ordering = PageOrdering::new(rules);
     candidates
         .map(|p| p.collect::<Vec<u32>>())
-        .filter(|pages| {
-            let mut seen: HashSet<u32> = HashSet::new();
-            for page in pages.iter() {
-                if match rules.get(page) {
-                    Some(rule) => rule.intersection(&seen).count() > 0,
-                    None => false,
-                } {
-                    return false;
-                }
-                seen.insert(*page);
-            }
-            true
-        })
+        .filter(|pages| ordering.is_valid(pages))
         .map(|pages| pages[pages.len() / 2])
         .sum()
 }
@@ -51,34 +272,17 @@ pub fn part1(input: &str) -> u32 {
 #[allow(dead_code)]
 pub fn part2(input: &str) -> u32 {
     let (rules, candidates) = parse(input);
+    let ordering = PageOrdering::new(rules);
     candidates
         .map(|p| p.collect::<Vec<u32>>())
-        .filter(|pages| {
-            let mut seen: HashSet<u32> = HashSet::new();
-            for page in pages.iter() {
-                if match rules.get(page) {
-                    Some(rule) => rule.intersection(&seen).count() > 0,
-                    None => false,
-                } {
-                    return true;
-                }
-                seen.insert(*page);
-            }
-            false
-        })
-        .map(|mut pages| {
-            pages.sort_by(|a, b| {
-                if rules.get(a).map(|after| after.contains(b)).unwrap_or(false) {
-                    Ordering::Less
-                } else if rules.get(b).map(|after| after.contains(a)).unwrap_or(false) {
-                    Ordering::Greater
-                } else {
-                    Ordering::Equal
-                }
-            });
-            pages
+        .filter(|pages| !ordering.is_valid(pages))
+        .map(|pages| {
+            let repair = ordering.repair(&pages);
+            let order = repair
+                .order()
+                .expect("Rules between the pages form a cycle");
+            order[order.len() / 2]
         })
-        .map(|pages| pages[pages.len() / 2])
         .sum()
 }
 
@@ -131,6 +335,103 @@ mod tests {
         assert_eq!(part2(EXAMPLE), 123);
     }
 
+    #[test]
+    fn violations_example() {
+        let (rules, _) = parse(EXAMPLE);
+        let ordering = PageOrdering::new(rules);
+        assert_eq!(ordering.violations(&[75, 47, 61, 53, 29]), vec![]);
+        assert_eq!(ordering.violations(&[75, 97, 47, 61, 53]), vec![(97, 75)]);
+        assert_eq!(
+            ordering.violations(&[97, 13, 75, 29, 47]),
+            vec![(75, 13), (29, 13), (47, 13), (47, 29)]
+        );
+    }
+
+    #[test]
+    fn repair_example() {
+        let (rules, _) = parse(EXAMPLE);
+        let ordering = PageOrdering::new(rules);
+        assert_eq!(
+            ordering.repair(&[75, 97, 47, 61, 53]),
+            Repair::Unique(vec![97, 75, 47, 61, 53])
+        );
+        assert_eq!(
+            ordering.repair(&[61, 13, 29]),
+            Repair::Unique(vec![61, 29, 13])
+        );
+        assert_eq!(
+            ordering.repair(&[97, 13, 75, 29, 47]),
+            Repair::Unique(vec![97, 75, 47, 29, 13])
+        );
+    }
+
+    #[test]
+    fn repair_ambiguous() {
+        // nothing says which of 2 and 3 goes first, so they keep their order in the update
+        let (rules, _) = parse("1|2\n1|3\n\n3,2,1\n");
+        let ordering = PageOrdering::new(rules);
+        assert_eq!(
+            ordering.repair(&[3, 2, 1]),
+            Repair::Ambiguous {
+                order: vec![1, 3, 2],
+                unordered: (3, 2),
+            }
+        );
+        // pages without any rules between them can go anywhere
+        assert_eq!(
+            ordering.repair(&[4, 1, 2]),
+            Repair::Ambiguous {
+                order: vec![4, 1, 2],
+                unordered: (4, 1),
+            }
+        );
+        // a rule with a page missing from the update doesn't apply to it
+        assert_eq!(ordering.repair(&[2, 1]), Repair::Unique(vec![1, 2]));
+        assert_eq!(ordering.repair(&[3]), Repair::Unique(vec![3]));
+    }
+
+    #[test]
+    fn repair_cyclic() {
+        let (rules, _) = parse("1|2\n2|3\n3|1\n3|4\n\n1,2,3\n");
+        let ordering = PageOrdering::new(rules);
+        assert_eq!(
+            ordering.repair(&[4, 3, 2, 1]),
+            Repair::Cyclic(vec![3, 1, 2])
+        );
+        assert_eq!(ordering.repair(&[2, 1, 3]), Repair::Cyclic(vec![2, 3, 1]));
+        assert_eq!(ordering.repair(&[3, 1, 2]).order(), None);
+        // without all three pages there is no loop
+        assert_eq!(ordering.repair(&[1, 3]), Repair::Unique(vec![3, 1]));
+        let (rules, _) = parse("5|5\n\n5\n");
+        assert_eq!(
+            PageOrdering::new(rules).repair(&[5]),
+            Repair::Cyclic(vec![5])
+        );
+    }
+
+    #[test]
+    fn rule_report_example() {
+        let report = rule_report(EXAMPLE);
+        assert_eq!(report.cycles, Vec::<Vec<u32>>::new());
+        // the example orders all seven pages, so only the rules between neighbours are needed
+        assert_eq!(report.redundant.len(), 15);
+        assert!(!report.redundant.contains(&(97, 75)));
+        assert!(report.redundant.contains(&(97, 13)));
+        assert_eq!(report.unconstrained, vec![]);
+        assert_eq!(report.unique_orders, vec![true; 6]);
+    }
+
+    #[test]
+    fn rule_report_adversarial() {
+        let report =
+            rule_report("1|2\n2|3\n1|3\n3|4\n4|2\n5|6\n6|5\n8|9\n\n1,2,3\n1,3\n7,1\n2,3,4\n9,8\n");
+        assert_eq!(report.cycles, vec![vec![2, 3, 4], vec![5, 6]]);
+        // 1|2 follows from 1|3, 3|4 and 4|2 just as much as 1|3 follows from 1|2 and 2|3
+        assert_eq!(report.redundant, vec![(1, 2), (1, 3)]);
+        assert_eq!(report.unconstrained, vec![7]);
+        assert_eq!(report.unique_orders, vec![true, true, false, false, true]);
+    }
+
     #[test]
     fn part1_test() {
         assert_eq!(
@@ -3,7 +3,7 @@ use advent_of_code::{
     parse::{parsers, Parser},
 };
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct GuardState {
@@ -26,11 +26,45 @@ impl GuardState {
     }
 }
 
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum TurnRule {
+    Right,
+    Left,
+    // right on the first turn, then left, then right again...
+    Alternate,
+    Reverse,
+}
+
+impl TurnRule {
+    fn turn(self, direction: Direction, turned_right_last: bool) -> Direction {
+        match self {
+            TurnRule::Right => direction.rotate_right(),
+            TurnRule::Left => direction.rotate_left(),
+            TurnRule::Alternate if turned_right_last => direction.rotate_left(),
+            TurnRule::Alternate => direction.rotate_right(),
+            TurnRule::Reverse => direction.rotate_right().rotate_right(),
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum Cell {
     Empty,
     Wall,
     Guard(Direction),
+    // can only be entered while moving in the given direction
+    OneWay(Direction),
+}
+
+impl Cell {
+    fn blocks(&self, direction: Direction) -> bool {
+        match self {
+            Cell::Wall => true,
+            Cell::OneWay(allowed) => *allowed != direction,
+            Cell::Empty | Cell::Guard(_) => false,
+        }
+    }
 }
 
 fn parse(input: &str) -> Grid<Cell> {
@@ -40,44 +74,410 @@ fn parse(input: &str) -> Grid<Cell> {
         .or(parsers::tag_replace(">", Cell::Guard(Direction::East)))
         .or(parsers::tag_replace("<", Cell::Guard(Direction::West)))
         .or(parsers::tag_replace("v", Cell::Guard(Direction::South)))
+        .or(parsers::tag_replace("↑", Cell::OneWay(Direction::North)))
+        .or(parsers::tag_replace("→", Cell::OneWay(Direction::East)))
+        .or(parsers::tag_replace("↓", Cell::OneWay(Direction::South)))
+        .or(parsers::tag_replace("←", Cell::OneWay(Direction::West)))
         .grid("", "\n")
         .parse(input)
         .finish()
         .expect("Failed to parse input")
 }
 
-// Returns Some(visited cells) if the guard exits, or None if it gets caught in a loop
-fn simulate(grid: &Grid<Cell>, mut guard_state: GuardState) -> Option<HashSet<GuardState>> {
-    let mut visited_positions: HashSet<GuardState> = HashSet::new();
-    visited_positions.insert(guard_state);
+fn find_guards(grid: &Grid<Cell>) -> Vec<GuardState> {
+    grid.iter_points()
+        .filter_map(|position| match grid.get(position) {
+            Ok(Cell::Guard(direction)) => Some(GuardState {
+                position,
+                direction: *direction,
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+fn find_guard(grid: &Grid<Cell>) -> GuardState {
+    find_guards(grid)[0]
+}
+
+// Every state the guard passes through in order, and whether the guard walks off the grid or
+// gets caught in a loop
+enum Patrol {
+    Exits {
+        path: Vec<GuardState>,
+    },
+    Loops {
+        path: Vec<GuardState>,
+        cycle_start: usize,
+    },
+}
+
+impl Patrol {
+    fn exit_path(self) -> Option<Vec<GuardState>> {
+        match self {
+            Patrol::Exits { path } => Some(path),
+            Patrol::Loops { .. } => None,
+        }
+    }
+}
+
+fn simulate(grid: &Grid<Cell>, mut guard_state: GuardState) -> Patrol {
+    let mut path: Vec<GuardState> = vec![guard_state];
+    let mut visited_positions: HashMap<GuardState, usize> = HashMap::from([(guard_state, 0)]);
     while let Some(next_state) = guard_state.step(grid.dimensions()) {
-        if grid.get(next_state.position) == Ok(&Cell::Wall) {
+        if grid
+            .get(next_state.position)
+            .is_ok_and(|cell| cell.blocks(guard_state.direction))
+        {
             guard_state.rotate();
         } else {
             guard_state = next_state;
         }
-        if visited_positions.contains(&guard_state) {
-            return None;
+        if let Some(&cycle_start) = visited_positions.get(&guard_state) {
+            return Patrol::Loops { path, cycle_start };
+        }
+        visited_positions.insert(guard_state, path.len());
+        path.push(guard_state);
+    }
+    Patrol::Exits { path }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PatrolOutcome {
+    Exits,
+    Loops { cycle_start: usize },
+    Collides { position: GridPoint<usize> },
+}
+
+// Positions of every guard at each tick, with None once a guard has left the grid
+#[allow(dead_code)]
+struct GuardsPatrol {
+    ticks: Vec<Vec<Option<GuardState>>>,
+    outcome: PatrolOutcome,
+}
+
+// Several guards patrolling in lockstep under a shared turn rule. The patrol stops once every
+// guard has left the grid, the guards repeat an earlier configuration, or two guards meet
+#[allow(dead_code)]
+fn simulate_guards(grid: &Grid<Cell>, guards: &[GuardState], turn_rule: TurnRule) -> GuardsPatrol {
+    let mut current: Vec<Option<(GuardState, bool)>> =
+        guards.iter().map(|guard| Some((*guard, false))).collect();
+    let mut ticks: Vec<Vec<Option<GuardState>>> = Vec::new();
+    let mut seen: HashMap<Vec<Option<(GuardState, bool)>>, usize> = HashMap::new();
+    loop {
+        if current.iter().all(|guard| guard.is_none()) {
+            return GuardsPatrol {
+                ticks,
+                outcome: PatrolOutcome::Exits,
+            };
+        }
+        if let Some(&cycle_start) = seen.get(&current) {
+            return GuardsPatrol {
+                ticks,
+                outcome: PatrolOutcome::Loops { cycle_start },
+            };
+        }
+        seen.insert(current.clone(), ticks.len());
+        ticks.push(
+            current
+                .iter()
+                .map(|guard| guard.map(|(state, _)| state))
+                .collect(),
+        );
+
+        let next: Vec<Option<(GuardState, bool)>> = current
+            .iter()
+            .map(|guard| {
+                let (mut state, turned_right_last) = (*guard)?;
+                let next_state = state.step(grid.dimensions())?;
+                if grid
+                    .get(next_state.position)
+                    .is_ok_and(|cell| cell.blocks(state.direction))
+                {
+                    state.direction = turn_rule.turn(state.direction, turned_right_last);
+                    Some((state, !turned_right_last))
+                } else {
+                    Some((next_state, turned_right_last))
+                }
+            })
+            .collect();
+
+        let previous_positions: Vec<Option<GridPoint<usize>>> = current
+            .iter()
+            .map(|guard| guard.map(|(state, _)| state.position))
+            .collect();
+        let positions: Vec<Option<GridPoint<usize>>> = next
+            .iter()
+            .map(|guard| guard.map(|(state, _)| state.position))
+            .collect();
+        // guards collide by landing on the same cell or by walking through each other
+        let collision = (0..positions.len())
+            .flat_map(|i| (i + 1..positions.len()).map(move |j| (i, j)))
+            .find_map(|(i, j)| {
+                let position = positions[i]?;
+                if positions[j] == Some(position)
+                    || (previous_positions[j] == Some(position)
+                        && positions[j] == previous_positions[i])
+                {
+                    Some(position)
+                } else {
+                    None
+                }
+            });
+        current = next;
+        if let Some(position) = collision {
+            ticks.push(
+                current
+                    .iter()
+                    .map(|guard| guard.map(|(state, _)| state))
+                    .collect(),
+            );
+            return GuardsPatrol {
+                ticks,
+                outcome: PatrolOutcome::Collides { position },
+            };
+        }
+    }
+}
+
+// Draws the patrol like the puzzle text, with | and - for vertical and horizontal movement and +
+// where the two meet
+#[allow(dead_code)]
+fn render(grid: &Grid<Cell>, path: &[GuardState], obstruction: Option<GridPoint<usize>>) -> String {
+    let mut vertical: HashSet<GridPoint<usize>> = HashSet::new();
+    let mut horizontal: HashSet<GridPoint<usize>> = HashSet::new();
+    for guard_state in path {
+        match guard_state.direction {
+            Direction::North | Direction::South => vertical.insert(guard_state.position),
+            Direction::East | Direction::West => horizontal.insert(guard_state.position),
+        };
+    }
+    let rows = grid.iter_points().map(|p| p.row + 1).max().unwrap_or(0);
+    let cols = grid.iter_points().map(|p| p.col + 1).max().unwrap_or(0);
+    let mut rendered = String::new();
+    for row in 0..rows {
+        for col in 0..cols {
+            let position = GridPoint::new(row, col);
+            rendered.push(if Some(position) == obstruction {
+                'O'
+            } else {
+                match grid.get(position) {
+                    Ok(Cell::Wall) => '#',
+                    Ok(Cell::Guard(Direction::North)) => '^',
+                    Ok(Cell::Guard(Direction::East)) => '>',
+                    Ok(Cell::Guard(Direction::South)) => 'v',
+                    Ok(Cell::Guard(Direction::West)) => '<',
+                    Ok(Cell::OneWay(Direction::North)) => '↑',
+                    Ok(Cell::OneWay(Direction::East)) => '→',
+                    Ok(Cell::OneWay(Direction::South)) => '↓',
+                    Ok(Cell::OneWay(Direction::West)) => '←',
+                    _ => match (vertical.contains(&position), horizontal.contains(&position)) {
+                        (true, true) => '+',
+                        (true, false) => '|',
+                        (false, true) => '-',
+                        (false, false) => '.',
+                    },
+                }
+            });
         }
-        visited_positions.insert(guard_state);
+        rendered.push('\n');
+    }
+    rendered
+}
+
+#[derive(Debug)]
+struct LoopReport {
+    obstruction: GridPoint<usize>,
+    path: Vec<GuardState>,
+    cycle_start: usize,
+}
+
+impl LoopReport {
+    #[allow(dead_code)]
+    fn cycle(&self) -> &[GuardState] {
+        &self.path[self.cycle_start..]
+    }
+}
+
+// Every obstruction that traps the guard in a loop, ordered by position
+#[allow(dead_code)]
+fn loop_reports(grid: &Grid<Cell>) -> Vec<LoopReport> {
+    let initial_guard_state = find_guard(grid);
+    let mut reports: Vec<LoopReport> = simulate(grid, initial_guard_state)
+        .exit_path()
+        .unwrap()
+        .into_iter()
+        .map(|guard_state| guard_state.position)
+        .filter(|position| *position != initial_guard_state.position)
+        .collect::<HashSet<GridPoint<usize>>>()
+        .into_iter()
+        .filter_map(|obstruction| {
+            let mut new_grid = grid.clone();
+            new_grid.set(obstruction, Cell::Wall).unwrap();
+            match simulate(&new_grid, initial_guard_state) {
+                Patrol::Loops { path, cycle_start } => Some(LoopReport {
+                    obstruction,
+                    path,
+                    cycle_start,
+                }),
+                Patrol::Exits { .. } => None,
+            }
+        })
+        .collect();
+    reports.sort_by_key(|report| (report.obstruction.row, report.obstruction.col));
+    reports
+}
+
+// Headings in clockwise order, along with their row and column steps
+const HEADINGS: [Direction; 4] = [
+    Direction::North,
+    Direction::East,
+    Direction::South,
+    Direction::West,
+];
+const HEADING_DELTAS: [(isize, isize); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+
+// Bitset over (cell, heading) pairs
+struct VisitedSet(Vec<u64>);
+
+impl VisitedSet {
+    fn new(size: usize) -> Self {
+        VisitedSet(vec![0; size.div_ceil(64)])
+    }
+
+    fn clear(&mut self) {
+        self.0.fill(0);
+    }
+
+    // Returns whether the state was newly inserted, like HashSet::insert
+    fn insert(&mut self, state: usize) -> bool {
+        let mask = 1 << (state % 64);
+        let newly_inserted = self.0[state / 64] & mask == 0;
+        self.0[state / 64] |= mask;
+        newly_inserted
+    }
+}
+
+// For every cell and heading, the cell the guard stops on in front of the next wall, or None if
+// it walks off the grid. A single extra obstruction is patched in at lookup time
+struct JumpTable {
+    rows: usize,
+    cols: usize,
+    stops: Vec<[Option<usize>; 4]>,
+}
+
+impl JumpTable {
+    fn new(grid: &Grid<Cell>) -> Self {
+        let rows = grid.iter_points().map(|p| p.row + 1).max().unwrap_or(0);
+        let cols = grid.iter_points().map(|p| p.col + 1).max().unwrap_or(0);
+        let blocked: Vec<[bool; 4]> = (0..rows * cols)
+            .map(|idx| {
+                let cell = grid.get(GridPoint::new(idx / cols, idx % cols)).ok();
+                HEADINGS.map(|direction| cell.is_some_and(|cell| cell.blocks(direction)))
+            })
+            .collect();
+        let mut table = JumpTable {
+            rows,
+            cols,
+            stops: vec![[None; 4]; rows * cols],
+        };
+        for (heading, (row_delta, col_delta)) in HEADING_DELTAS.into_iter().enumerate() {
+            // visit cells so the one ahead of the guard is always filled in first
+            let order: Vec<usize> = if row_delta + col_delta < 0 {
+                (0..rows * cols).collect()
+            } else {
+                (0..rows * cols).rev().collect()
+            };
+            for idx in order {
+                table.stops[idx][heading] = match table.offset(idx, heading, 1) {
+                    None => None,
+                    Some(ahead) if blocked[ahead][heading] => Some(idx),
+                    Some(ahead) => table.stops[ahead][heading],
+                };
+            }
+        }
+        table
+    }
+
+    fn index(&self, position: GridPoint<usize>) -> usize {
+        position.row * self.cols + position.col
+    }
+
+    fn offset(&self, idx: usize, heading: usize, distance: usize) -> Option<usize> {
+        let (row_delta, col_delta) = HEADING_DELTAS[heading];
+        let row = (idx / self.cols).checked_add_signed(row_delta * distance as isize)?;
+        let col = (idx % self.cols).checked_add_signed(col_delta * distance as isize)?;
+        if row < self.rows && col < self.cols {
+            Some(row * self.cols + col)
+        } else {
+            None
+        }
+    }
+
+    // How far `to` is in front of `from` when facing heading, if it is in front at all
+    fn distance_ahead(&self, from: usize, heading: usize, to: usize) -> Option<usize> {
+        let (from_row, from_col) = (from / self.cols, from % self.cols);
+        let (to_row, to_col) = (to / self.cols, to % self.cols);
+        match heading {
+            0 if from_col == to_col && to_row <= from_row => Some(from_row - to_row),
+            1 if from_row == to_row && to_col >= from_col => Some(to_col - from_col),
+            2 if from_col == to_col && to_row >= from_row => Some(to_row - from_row),
+            3 if from_row == to_row && to_col <= from_col => Some(from_col - to_col),
+            _ => None,
+        }
+    }
+
+    fn stop(&self, idx: usize, heading: usize, obstruction: usize) -> Option<usize> {
+        let stop = self.stops[idx][heading];
+        let stop_distance = stop.map_or(usize::MAX, |stop| {
+            self.distance_ahead(idx, heading, stop).unwrap()
+        });
+        match self.distance_ahead(idx, heading, obstruction) {
+            Some(obstruction_distance)
+                if obstruction_distance > 0 && obstruction_distance <= stop_distance =>
+            {
+                self.offset(idx, heading, obstruction_distance - 1)
+            }
+            _ => stop,
+        }
+    }
+
+    fn loops(
+        &self,
+        start: usize,
+        heading: usize,
+        obstruction: usize,
+        visited: &mut VisitedSet,
+    ) -> bool {
+        visited.clear();
+        let (mut idx, mut heading) = (start, heading);
+        while let Some(stop) = self.stop(idx, heading, obstruction) {
+            idx = stop;
+            heading = (heading + 1) % 4;
+            if !visited.insert(idx * 4 + heading) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+fn heading_index(direction: Direction) -> usize {
+    match direction {
+        Direction::North => 0,
+        Direction::East => 1,
+        Direction::South => 2,
+        Direction::West => 3,
     }
-    Some(visited_positions)
 }
 
 #[allow(dead_code)]
 pub fn part1(input: &str) -> usize {
     let grid = parse(input);
-    let guard_state = grid
-        .iter_points()
-        .find_map(|position| match grid.get(position) {
-            Ok(Cell::Guard(direction)) => Some(GuardState {
-                position,
-                direction: *direction,
-            }),
-            _ => None,
-        })
-        .unwrap();
+    let guard_state = find_guard(&grid);
     simulate(&grid, guard_state)
+        .exit_path()
         .unwrap()
         .into_iter()
         .map(|guard_state| guard_state.position)
@@ -88,26 +488,46 @@ pub fn part1(input: &str) -> usize {
 #[allow(dead_code)]
 pub fn part2(input: &str) -> usize {
     let grid = parse(input);
-    let initial_guard_state = grid
-        .iter_points()
-        .find_map(|position| match grid.get(position) {
-            Ok(Cell::Guard(direction)) => Some(GuardState {
-                position,
-                direction: *direction,
-            }),
-            _ => None,
+    let initial_guard_state = find_guard(&grid);
+    let jump_table = JumpTable::new(&grid);
+    let start = jump_table.index(initial_guard_state.position);
+    let heading = heading_index(initial_guard_state.direction);
+    let mut visited = VisitedSet::new(jump_table.rows * jump_table.cols * 4);
+    simulate(&grid, initial_guard_state)
+        .exit_path()
+        .unwrap()
+        .into_iter()
+        .map(|guard_state| guard_state.position)
+        .filter(|position| *position != initial_guard_state.position)
+        .collect::<HashSet<GridPoint<usize>>>()
+        .into_iter()
+        .filter(|position| {
+            jump_table.loops(start, heading, jump_table.index(*position), &mut visited)
         })
-        .unwrap();
+        .count()
+}
+
+#[allow(dead_code)]
+fn part2_brute_force(input: &str) -> usize {
+    let grid = parse(input);
+    let initial_guard_state = find_guard(&grid);
     simulate(&grid, initial_guard_state)
+        .exit_path()
         .unwrap()
         .into_iter()
         .filter_map(|guard_state| {
-            // consider adding a block right in front of the guard
+            // consider adding a block right in front of the guard, where nothing stops it already
             let next_state = guard_state.step(grid.dimensions())?;
-            if grid.get(next_state.position) != Ok(&Cell::Wall) {
+            if grid
+                .get(next_state.position)
+                .is_ok_and(|cell| !cell.blocks(guard_state.direction))
+            {
                 let mut new_grid = grid.clone();
                 new_grid.set(next_state.position, Cell::Wall).unwrap();
-                if simulate(&new_grid, initial_guard_state).is_none() {
+                if simulate(&new_grid, initial_guard_state)
+                    .exit_path()
+                    .is_none()
+                {
                     return Some(next_state.position);
                 }
             }
@@ -148,6 +568,174 @@ mod tests {
         assert_eq!(part2(EXAMPLE), 6);
     }
 
+    #[test]
+    fn part2_brute_force_example() {
+        assert_eq!(part2_brute_force(EXAMPLE), 6);
+    }
+
+    #[test]
+    fn render_example() {
+        let grid = parse(EXAMPLE);
+        let path = simulate(&grid, find_guard(&grid)).exit_path().unwrap();
+        assert_eq!(
+            render(&grid, &path, None),
+            "....#.....
+....+---+#
+....|...|.
+..#.|...|.
+..+-+-+#|.
+..|.|.|.|.
+.#+-^-+-+.
+.+----++#.
+#+----+|..
+......#|..
+"
+        );
+    }
+
+    #[test]
+    fn loop_reports_example() {
+        let grid = parse(EXAMPLE);
+        let reports = loop_reports(&grid);
+        assert_eq!(
+            reports
+                .iter()
+                .map(|report| (report.obstruction, report.cycle().len(), report.cycle()[0]))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    GridPoint::new(6, 3),
+                    22,
+                    GuardState {
+                        position: GridPoint::new(6, 4),
+                        direction: Direction::North
+                    }
+                ),
+                (
+                    GridPoint::new(7, 6),
+                    16,
+                    GuardState {
+                        position: GridPoint::new(6, 6),
+                        direction: Direction::West
+                    }
+                ),
+                (
+                    GridPoint::new(7, 7),
+                    16,
+                    GuardState {
+                        position: GridPoint::new(7, 6),
+                        direction: Direction::South
+                    }
+                ),
+                (
+                    GridPoint::new(8, 1),
+                    20,
+                    GuardState {
+                        position: GridPoint::new(6, 2),
+                        direction: Direction::North
+                    }
+                ),
+                (
+                    GridPoint::new(8, 3),
+                    42,
+                    GuardState {
+                        position: GridPoint::new(6, 4),
+                        direction: Direction::North
+                    }
+                ),
+                (
+                    GridPoint::new(9, 7),
+                    18,
+                    GuardState {
+                        position: GridPoint::new(8, 6),
+                        direction: Direction::West
+                    }
+                ),
+            ]
+        );
+        let mut new_grid = grid.clone();
+        new_grid.set(reports[5].obstruction, Cell::Wall).unwrap();
+        assert_eq!(
+            render(&new_grid, &reports[5].path, Some(reports[5].obstruction)),
+            "....#.....
+....+---+#
+....|...|.
+..#.|...|.
+..+-+-+#|.
+..|.|.|.|.
+.#+-^-+-+.
+.+----++#.
+#+----++..
+......#O..
+"
+        );
+    }
+
+    #[test]
+    fn one_way_tiles() {
+        assert_eq!(part1("#\n↓\n^\n"), 1);
+        assert_eq!(part1("#\n↑\n^\n"), 2);
+        assert_eq!(
+            part2("....\n.→..\n.^..\n"),
+            part2_brute_force("....\n.→..\n.^..\n")
+        );
+    }
+
+    #[test]
+    fn simulate_guards_turn_rules() {
+        let grid = parse(EXAMPLE);
+        let guards = find_guards(&grid);
+        for (turn_rule, ticks, positions) in [
+            (TurnRule::Right, 55, 41),
+            (TurnRule::Left, 11, 10),
+            (TurnRule::Reverse, 15, 9),
+            (TurnRule::Alternate, 13, 11),
+        ] {
+            let patrol = simulate_guards(&grid, &guards, turn_rule);
+            assert_eq!(patrol.outcome, PatrolOutcome::Exits);
+            assert_eq!(patrol.ticks.len(), ticks);
+            assert_eq!(
+                patrol
+                    .ticks
+                    .iter()
+                    .flatten()
+                    .flatten()
+                    .map(|guard_state| guard_state.position)
+                    .collect::<HashSet<GridPoint<usize>>>()
+                    .len(),
+                positions
+            );
+        }
+
+        let mut blocked_grid = grid.clone();
+        blocked_grid.set(GridPoint::new(6, 3), Cell::Wall).unwrap();
+        let patrol = simulate_guards(&blocked_grid, &guards, TurnRule::Right);
+        assert_eq!(patrol.outcome, PatrolOutcome::Loops { cycle_start: 0 });
+        assert_eq!(patrol.ticks.len(), 22);
+    }
+
+    #[test]
+    fn simulate_guards_collisions() {
+        let grid = parse(">...<\n");
+        let patrol = simulate_guards(&grid, &find_guards(&grid), TurnRule::Right);
+        assert_eq!(
+            patrol.outcome,
+            PatrolOutcome::Collides {
+                position: GridPoint::new(0, 2)
+            }
+        );
+        assert_eq!(patrol.ticks.len(), 3);
+
+        let grid = parse(">..<\n");
+        let patrol = simulate_guards(&grid, &find_guards(&grid), TurnRule::Right);
+        assert_eq!(
+            patrol.outcome,
+            PatrolOutcome::Collides {
+                position: GridPoint::new(0, 2)
+            }
+        );
+    }
+
     #[test]
     fn part1_test() {
         assert_eq!(
@@ -197,4 +785,16 @@ mod tests {
             ))
         });
     }
+
+    #[bench]
+    fn part2_brute_force_bench(b: &mut Bencher) {
+        b.iter(|| {
+            part2_brute_force(&load_question_input(
+                crate::YEAR,
+                crate::COOKIE_PATH,
+                crate::INPUT_CACHE,
+                DAY,
+            ))
+        });
+    }
 }
@@ -1,60 +1,435 @@
-use std::collections::HashSet;
+use std::{collections::HashMap, fmt};
 
 use advent_of_code::parse::{parsers, Parser};
 
-fn parse(input: &str) -> impl Iterator<Item = (u64, impl Iterator<Item = u64>)> {
+// What an operator's left operand must have been to give a result with a known right operand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Inverse {
+    Impossible,
+    Unique(u64),
+    // the right operand alone decides the result, like multiplying by zero
+    Any,
+}
+
+pub trait Operator {
+    fn symbol(&self) -> &'static str;
+
+    // None when the result overflows or is not a non-negative integer
+    fn apply(&self, left: u64, right: u64) -> Option<u64>;
+
+    fn undo(&self, result: u64, right: u64) -> Inverse;
+
+    // Whether the result is never smaller than the left operand when the right one is positive,
+    // which lets a running total that has passed the target be abandoned
+    fn grows(&self) -> bool {
+        false
+    }
+}
+
+pub struct Add;
+pub struct Multiply;
+pub struct Concatenate;
+#[allow(dead_code)]
+pub struct Subtract;
+#[allow(dead_code)]
+pub struct Divide;
+#[allow(dead_code)]
+pub struct Power;
+
+impl Operator for Add {
+    fn symbol(&self) -> &'static str {
+        "+"
+    }
+
+    fn apply(&self, left: u64, right: u64) -> Option<u64> {
+        left.checked_add(right)
+    }
+
+    fn undo(&self, result: u64, right: u64) -> Inverse {
+        result
+            .checked_sub(right)
+            .map_or(Inverse::Impossible, Inverse::Unique)
+    }
+
+    fn grows(&self) -> bool {
+        true
+    }
+}
+
+impl Operator for Multiply {
+    fn symbol(&self) -> &'static str {
+        "*"
+    }
+
+    fn apply(&self, left: u64, right: u64) -> Option<u64> {
+        left.checked_mul(right)
+    }
+
+    fn undo(&self, result: u64, right: u64) -> Inverse {
+        match (result, right) {
+            (0, 0) => Inverse::Any,
+            (_, 0) => Inverse::Impossible,
+            _ if result.is_multiple_of(right) => Inverse::Unique(result / right),
+            _ => Inverse::Impossible,
+        }
+    }
+
+    fn grows(&self) -> bool {
+        true
+    }
+}
+
+// The power of ten that shifts a number left past all of `right`'s digits, if it fits
+fn digit_scale(right: u64) -> Option<u64> {
+    10_u64.checked_pow(right.checked_ilog10().unwrap_or(0) + 1)
+}
+
+impl Operator for Concatenate {
+    fn symbol(&self) -> &'static str {
+        "||"
+    }
+
+    fn apply(&self, left: u64, right: u64) -> Option<u64> {
+        left.checked_mul(digit_scale(right)?)?.checked_add(right)
+    }
+
+    fn undo(&self, result: u64, right: u64) -> Inverse {
+        match digit_scale(right) {
+            Some(scale) if result % scale == right => Inverse::Unique(result / scale),
+            _ => Inverse::Impossible,
+        }
+    }
+
+    fn grows(&self) -> bool {
+        true
+    }
+}
+
+impl Operator for Subtract {
+    fn symbol(&self) -> &'static str {
+        "-"
+    }
+
+    fn apply(&self, left: u64, right: u64) -> Option<u64> {
+        left.checked_sub(right)
+    }
+
+    fn undo(&self, result: u64, right: u64) -> Inverse {
+        result
+            .checked_add(right)
+            .map_or(Inverse::Impossible, Inverse::Unique)
+    }
+}
+
+// Division that only applies when there is no remainder
+impl Operator for Divide {
+    fn symbol(&self) -> &'static str {
+        "/"
+    }
+
+    fn apply(&self, left: u64, right: u64) -> Option<u64> {
+        (right != 0 && left.is_multiple_of(right)).then(|| left / right)
+    }
+
+    fn undo(&self, result: u64, right: u64) -> Inverse {
+        match right {
+            0 => Inverse::Impossible,
+            _ => result
+                .checked_mul(right)
+                .map_or(Inverse::Impossible, Inverse::Unique),
+        }
+    }
+}
+
+impl Operator for Power {
+    fn symbol(&self) -> &'static str {
+        "^"
+    }
+
+    fn apply(&self, left: u64, right: u64) -> Option<u64> {
+        left.checked_pow(u32::try_from(right).ok()?)
+    }
+
+    fn undo(&self, result: u64, right: u64) -> Inverse {
+        match (result, right) {
+            (1, 0) => Inverse::Any,
+            (_, 0) => Inverse::Impossible,
+            (0 | 1, _) | (_, 1) => Inverse::Unique(result),
+            _ => {
+                // the integer root, if there is one, lies between 2 and the result itself
+                let (mut low, mut high) = (2, result);
+                while low < high {
+                    let middle = low + (high - low) / 2;
+                    match self.apply(middle, right) {
+                        Some(power) if power >= result => high = middle,
+                        Some(_) => low = middle + 1,
+                        None => high = middle,
+                    }
+                }
+                match self.apply(low, right) {
+                    Some(power) if power == result => Inverse::Unique(low),
+                    _ => Inverse::Impossible,
+                }
+            }
+        }
+    }
+
+    fn grows(&self) -> bool {
+        true
+    }
+}
+
+impl fmt::Display for dyn Operator + '_ {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+#[allow(dead_code)]
+pub const PART1_OPERATORS: [&dyn Operator; 2] = [&Add, &Multiply];
+#[allow(dead_code)]
+pub const PART2_OPERATORS: [&dyn Operator; 3] = [&Add, &Multiply, &Concatenate];
+
+type Assignment<'a> = Vec<&'a dyn Operator>;
+
+struct Equation {
+    target: u64,
+    components: Vec<u64>,
+}
+
+impl Equation {
+    // Operators that make the components evaluated left to right equal the target, found by
+    // undoing operators from the right so only the few totals that could lead to the target are
+    // ever considered. When several assignments work, the one returned is the first when they
+    // are compared from their last operator back to their first, each by its place in the order
+    // given, so with addition listed first "81 * 40 + 27" comes before "81 + 40 * 27".
+    fn solution<'a>(&self, operators: &[&'a dyn Operator]) -> Option<Assignment<'a>> {
+        self.solution_to(
+            self.components.len().checked_sub(1)?,
+            self.target,
+            operators,
+        )
+    }
+
+    // Operators that make the components up to `end` evaluate to `target`
+    fn solution_to<'a>(
+        &self,
+        end: usize,
+        target: u64,
+        operators: &[&'a dyn Operator],
+    ) -> Option<Assignment<'a>> {
+        if end == 0 {
+            return (self.components[0] == target).then(Vec::new);
+        }
+        operators.iter().find_map(|operator| {
+            let mut assignment = match operator.undo(target, self.components[end]) {
+                Inverse::Impossible => return None,
+                Inverse::Unique(left) => self.solution_to(end - 1, left, operators)?,
+                Inverse::Any => self.search_forward(end - 1, operators, None)?,
+            };
+            assignment.push(*operator);
+            Some(assignment)
+        })
+    }
+
+    // The first operators, trying them in the order given from the left, under which the
+    // components up to `end` evaluate without failing, to `target` if there is one
+    fn search_forward<'a>(
+        &self,
+        end: usize,
+        operators: &[&'a dyn Operator],
+        target: Option<u64>,
+    ) -> Option<Assignment<'a>> {
+        // a total past the target can only be abandoned if nothing can bring it back down
+        let only_grows = self.components[1..=end]
+            .iter()
+            .all(|component| *component > 0)
+            && operators.iter().all(|operator| operator.grows());
+        let mut assignment = Vec::with_capacity(end);
+        self.extend(
+            self.components[0],
+            &self.components[1..=end],
+            operators,
+            (target, only_grows),
+            &mut assignment,
+        )
+        .then_some(assignment)
+    }
+
+    fn extend<'a>(
+        &self,
+        total: u64,
+        rest: &[u64],
+        operators: &[&'a dyn Operator],
+        (target, only_grows): (Option<u64>, bool),
+        assignment: &mut Assignment<'a>,
+    ) -> bool {
+        let Some((next, rest)) = rest.split_first() else {
+            return target.is_none_or(|target| total == target);
+        };
+        if only_grows && target.is_some_and(|target| total > target) {
+            return false;
+        }
+        for operator in operators {
+            let Some(total) = operator.apply(total, *next) else {
+                continue;
+            };
+            assignment.push(*operator);
+            if self.extend(total, rest, operators, (target, only_grows), assignment) {
+                return true;
+            }
+            assignment.pop();
+        }
+        false
+    }
+
+    // Evaluates every assignment from the left, which the backwards solver avoids
+    #[allow(dead_code)]
+    fn forward_solution<'a>(&self, operators: &[&'a dyn Operator]) -> Option<Assignment<'a>> {
+        let end = self.components.len().checked_sub(1)?;
+        self.search_forward(end, operators, Some(self.target))
+    }
+
+    // How many different operator assignments make the equation true
+    fn ways(&self, operators: &[&dyn Operator]) -> u64 {
+        match self.components.len().checked_sub(1) {
+            Some(end) => self.ways_to(end, self.target, operators),
+            None => 0,
+        }
+    }
+
+    fn ways_to(&self, end: usize, target: u64, operators: &[&dyn Operator]) -> u64 {
+        if end == 0 {
+            return u64::from(self.components[0] == target);
+        }
+        operators
+            .iter()
+            .map(
+                |operator| match operator.undo(target, self.components[end]) {
+                    Inverse::Impossible => 0,
+                    Inverse::Unique(left) => self.ways_to(end - 1, left, operators),
+                    Inverse::Any => self
+                        .results(end - 1, operators)
+                        .values()
+                        .map(|(ways, _)| ways)
+                        .sum(),
+                },
+            )
+            .sum()
+    }
+
+    // Every total the components up to `end` can evaluate to, with how many assignments
+    // produce it and one that does
+    fn results<'a>(
+        &self,
+        end: usize,
+        operators: &[&'a dyn Operator],
+    ) -> HashMap<u64, (u64, Assignment<'a>)> {
+        self.components[1..=end].iter().fold(
+            HashMap::from([(self.components[0], (1, Vec::new()))]),
+            |reached, next| {
+                let mut results: HashMap<u64, (u64, Assignment<'a>)> = HashMap::new();
+                for (total, (ways, assignment)) in reached {
+                    for operator in operators {
+                        let Some(total) = operator.apply(total, *next) else {
+                            continue;
+                        };
+                        let entry = results
+                            .entry(total)
+                            .or_insert_with(|| (0, [assignment.as_slice(), &[*operator]].concat()));
+                        entry.0 += ways;
+                    }
+                }
+                results
+            },
+        )
+    }
+
+    fn expression(&self, assignment: &[&dyn Operator]) -> String {
+        let mut expression = self.components[0].to_string();
+        for (operator, component) in assignment.iter().zip(&self.components[1..]) {
+            expression.push_str(&format!(" {} {}", operator, component));
+        }
+        expression
+    }
+
+    fn calibrate(&self, operators: &[&dyn Operator]) -> Calibration {
+        if let Some(assignment) = self.solution(operators) {
+            return Calibration::Solved {
+                expression: self.expression(&assignment),
+                ways: self.ways(operators),
+            };
+        }
+        let results = self.results(self.components.len() - 1, operators);
+        let nearest = |below: bool| {
+            results
+                .iter()
+                .filter(|(total, _)| (**total < self.target) == below)
+                .min_by_key(|(total, _)| total.abs_diff(self.target))
+                .map(|(total, (_, assignment))| (*total, self.expression(assignment)))
+        };
+        Calibration::Unsolvable {
+            below: nearest(true),
+            above: nearest(false),
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum Calibration {
+    // one expression that works, and how many assignments of operators do
+    Solved {
+        expression: String,
+        ways: u64,
+    },
+    // the closest the operators get to the target from either side, with how they get there
+    Unsolvable {
+        below: Option<(u64, String)>,
+        above: Option<(u64, String)>,
+    },
+}
+
+fn parse(input: &str) -> Vec<Equation> {
     parsers::number()
         .skip_tag(": ")
         .and_then(parsers::number().list(" "))
+        .map(|(target, components)| Equation {
+            target,
+            components: components.collect(),
+        })
         .many_lines("\n")
         .parse(input)
         .finish()
         .expect("Failed to parse input")
+        .collect()
 }
 
+// Each calibration line's target with how the operators do or don't reach it
 #[allow(dead_code)]
-pub fn part1(input: &str) -> u64 {
+pub fn calibrate(input: &str, operators: &[&dyn Operator]) -> Vec<(u64, Calibration)> {
     parse(input)
-        .filter_map(|(target, mut components)| {
-            let first = components.next().unwrap();
-            if components
-                .fold(HashSet::from([first]), |seen, next| {
-                    seen.into_iter()
-                        .flat_map(|prev| [prev + next, prev * next])
-                        .collect()
-                })
-                .contains(&target)
-            {
-                Some(target)
-            } else {
-                None
-            }
-        })
+        .into_iter()
+        .map(|equation| (equation.target, equation.calibrate(operators)))
+        .collect()
+}
+
+fn total_calibration(input: &str, operators: &[&dyn Operator]) -> u64 {
+    parse(input)
+        .into_iter()
+        .filter(|equation| equation.solution(operators).is_some())
+        .map(|equation| equation.target)
         .sum()
 }
 
+#[allow(dead_code)]
+pub fn part1(input: &str) -> u64 {
+    total_calibration(input, &PART1_OPERATORS)
+}
+
 #[allow(dead_code)]
 pub fn part2(input: &str) -> u64 {
-    parse(input)
-        .filter_map(|(target, mut components)| {
-            let first = components.next().unwrap();
-            if components
-                .fold(HashSet::from([first]), |seen, next| {
-                    seen.into_iter()
-                        .flat_map(|prev| {
-                            let concat_scalar = 10_u64.pow(next.ilog10() + 1);
-                            [prev + next, prev * next, prev * concat_scalar + next]
-                        })
-                        .collect()
-                })
-                .contains(&target)
-            {
-                Some(target)
-            } else {
-                None
-            }
-        })
-        .sum()
+    total_calibration(input, &PART2_OPERATORS)
 }
 
 #[allow(dead_code)]
@@ -87,6 +462,138 @@ mod tests {
         assert_eq!(part2(EXAMPLE), 11387);
     }
 
+    #[test]
+    fn calibrate_example() {
+        let solved = |expression: &str, ways| Calibration::Solved {
+            expression: expression.to_string(),
+            ways,
+        };
+        // the last operator is the first one undone, so the assignment returned favours
+        // additions towards the end rather than the start
+        let calibrations = calibrate(EXAMPLE, &PART1_OPERATORS);
+        assert_eq!(calibrations[0], (190, solved("10 * 19", 1)));
+        assert_eq!(calibrations[1], (3267, solved("81 * 40 + 27", 2)));
+        assert_eq!(calibrations[8], (292, solved("11 + 6 * 16 + 20", 1)));
+        assert_eq!(
+            calibrations[2],
+            (
+                83,
+                Calibration::Unsolvable {
+                    below: Some((22, "17 + 5".to_string())),
+                    above: Some((85, "17 * 5".to_string())),
+                }
+            )
+        );
+        assert_eq!(
+            calibrations[5],
+            (
+                161011,
+                Calibration::Unsolvable {
+                    below: Some((2080, "16 * 10 * 13".to_string())),
+                    above: None,
+                }
+            )
+        );
+
+        let calibrations = calibrate(EXAMPLE, &PART2_OPERATORS);
+        assert_eq!(calibrations[3], (156, solved("15 || 6", 1)));
+        assert_eq!(calibrations[4], (7290, solved("6 * 8 || 6 * 15", 1)));
+        assert_eq!(calibrations[6], (192, solved("17 || 8 + 14", 1)));
+    }
+
+    #[test]
+    fn ways_count_every_assignment() {
+        assert_eq!(parse("4: 2 2\n")[0].ways(&PART1_OPERATORS), 2);
+        assert_eq!(parse("1: 1 1 1 1 1\n")[0].ways(&PART1_OPERATORS), 1);
+        assert_eq!(parse("16: 2 2 2 2\n")[0].ways(&PART1_OPERATORS), 2);
+        // multiplying by zero brings the total back down, so overshooting can't be ruled out
+        let zero = &parse("5: 7 1 0 5\n")[0];
+        assert_eq!(zero.ways(&PART1_OPERATORS), 2);
+        assert_eq!(
+            zero.expression(&zero.solution(&PART1_OPERATORS).unwrap()),
+            "7 + 1 * 0 + 5"
+        );
+    }
+
+    #[test]
+    fn backwards_matches_forward() {
+        for operators in [&PART1_OPERATORS[..], &PART2_OPERATORS[..]] {
+            for equation in parse(EXAMPLE) {
+                assert_eq!(
+                    equation.solution(operators).is_some(),
+                    equation.forward_solution(operators).is_some(),
+                    "{}",
+                    equation.target
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn custom_operators() {
+        let operators: [&dyn Operator; 4] = [&Add, &Subtract, &Divide, &Power];
+        let solve = |input: &str| {
+            let equation = &parse(input)[0];
+            equation
+                .solution(&operators)
+                .map(|assignment| equation.expression(&assignment))
+        };
+        assert_eq!(solve("3: 10 4 2\n"), Some("10 - 4 / 2".to_string()));
+        assert_eq!(solve("64: 2 3 2\n"), Some("2 ^ 3 ^ 2".to_string()));
+        assert_eq!(solve("81: 3 2 2\n"), Some("3 ^ 2 ^ 2".to_string()));
+        // 7 / 2 leaves a remainder, and nothing else gets back to 3
+        assert_eq!(solve("3: 7 2\n"), None);
+        // any total to the power of zero is one
+        assert_eq!(solve("1: 5 3 0\n"), Some("5 + 3 ^ 0".to_string()));
+        // whichever way 5 and 3 are combined, apart from 5 / 3 which leaves a remainder
+        assert_eq!(parse("1: 5 3 0\n")[0].ways(&operators), 3);
+    }
+
+    #[test]
+    fn inverses() {
+        assert_eq!(Multiply.undo(0, 0), Inverse::Any);
+        assert_eq!(Multiply.undo(12, 0), Inverse::Impossible);
+        assert_eq!(Multiply.undo(12, 5), Inverse::Impossible);
+        assert_eq!(Concatenate.undo(1234, 34), Inverse::Unique(12));
+        assert_eq!(Concatenate.undo(1234, 4), Inverse::Unique(123));
+        assert_eq!(Concatenate.undo(1234, 35), Inverse::Impossible);
+        // nothing can go in front of a number with too many digits to shift, not even zero
+        assert_eq!(Concatenate.undo(u64::MAX, u64::MAX), Inverse::Impossible);
+        assert_eq!(Add.undo(3, 5), Inverse::Impossible);
+        assert_eq!(Divide.undo(3, 0), Inverse::Impossible);
+        assert_eq!(Power.undo(1, 0), Inverse::Any);
+        assert_eq!(Power.undo(1024, 10), Inverse::Unique(2));
+        assert_eq!(Power.undo(1025, 10), Inverse::Impossible);
+        assert_eq!(Power.undo(u64::MAX, 2), Inverse::Impossible);
+    }
+
+    #[test]
+    fn overflow_is_not_a_solution() {
+        assert_eq!(Concatenate.apply(u64::MAX / 10, 99), None);
+        assert_eq!(Concatenate.apply(0, u64::MAX), None);
+        assert_eq!(Multiply.apply(u64::MAX, 2), None);
+        assert_eq!(Power.apply(2, 64), None);
+        let huge = &parse("18446744073709551615: 18446744073709551615 1 1\n")[0];
+        assert_eq!(huge.ways(&PART2_OPERATORS), 1);
+        let expression = |assignment: Option<Assignment>| huge.expression(&assignment.unwrap());
+        assert_eq!(
+            expression(huge.solution(&PART2_OPERATORS)),
+            "18446744073709551615 * 1 * 1"
+        );
+        assert_eq!(
+            expression(huge.forward_solution(&PART2_OPERATORS)),
+            "18446744073709551615 * 1 * 1"
+        );
+        let unshiftable = &parse(
+            "18446744073709551615: 0 18446744073709551615
+",
+        )[0];
+        let operators: [&dyn Operator; 1] = [&Concatenate];
+        assert!(unshiftable.solution(&operators).is_none());
+        assert!(unshiftable.forward_solution(&operators).is_none());
+        assert_eq!(unshiftable.ways(&operators), 0);
+    }
+
     #[test]
     fn part1_test() {
         assert_eq!(
@@ -136,4 +643,16 @@ mod tests {
             ))
         });
     }
+
+    #[bench]
+    fn part2_forward_bench(b: &mut Bencher) {
+        let input = load_question_input(crate::YEAR, crate::COOKIE_PATH, crate::INPUT_CACHE, DAY);
+        b.iter(|| {
+            parse(&input)
+                .into_iter()
+                .filter(|equation| equation.forward_solution(&PART2_OPERATORS).is_some())
+                .map(|equation| equation.target)
+                .sum::<u64>()
+        });
+    }
 }
@@ -1,4 +1,8 @@
 use advent_of_code::parse::{parsers, Parser};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct File {
@@ -42,10 +46,23 @@ struct EmptyBlock {
     length: u64,
 }
 
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Eq)]
+struct DiskStats {
+    fragments: usize,
+    fragmented_files: usize,
+    free_blocks: u64,
+    free_spans: usize,
+    largest_free_run: u64,
+}
+
+// Files are kept in order of location. Empty blocks only cover the gaps between files, while size
+// also counts any free space after the last file
 #[derive(Debug)]
 struct Disk {
     files: Vec<File>,
     empty_blocks: Vec<EmptyBlock>,
+    size: u64,
 }
 
 impl Disk {
@@ -53,7 +70,37 @@ impl Disk {
         Disk {
             files: Vec::new(),
             empty_blocks: Vec::new(),
+            size: 0,
+        }
+    }
+
+    // Rebuilds a disk from files in any order, merging touching fragments of the same file
+    fn from_files(files: impl IntoIterator<Item = File>, size: u64) -> Self {
+        let mut files: Vec<File> = files.into_iter().filter(|f| f.length > 0).collect();
+        files.sort_by_key(|f| f.location);
+        let mut disk = Disk {
+            files: Vec::new(),
+            empty_blocks: Vec::new(),
+            size,
+        };
+        for file in files {
+            match disk.files.last_mut() {
+                Some(last) if last.id == file.id && last.end_location() == file.location => {
+                    last.length += file.length;
+                }
+                _ => {
+                    let last_end = disk.files.last().map(|f| f.end_location()).unwrap_or(0);
+                    if file.location != last_end {
+                        disk.empty_blocks.push(EmptyBlock {
+                            location: last_end,
+                            length: file.location - last_end,
+                        });
+                    }
+                    disk.files.push(file);
+                }
+            }
         }
+        disk
     }
 
     fn append_file(&mut self, location: u64, length: u64) {
@@ -73,11 +120,135 @@ impl Disk {
                 length: location - last_end,
             });
         }
+        self.size = location + length;
+    }
+
+    // The same disk read from the other end
+    fn mirrored(&self) -> Self {
+        Disk::from_files(
+            self.files.iter().map(|f| File {
+                location: self.size - f.end_location(),
+                ..*f
+            }),
+            self.size,
+        )
+    }
+
+    fn checksum(&self) -> u64 {
+        self.files.iter().map(|f| f.checksum()).sum()
+    }
+
+    fn file_at(&self, block: u64) -> Option<u64> {
+        let file = self.files[..self.files.partition_point(|f| f.location <= block)].last()?;
+        if block < file.end_location() {
+            Some(file.id)
+        } else {
+            None
+        }
+    }
+
+    // Gaps between files, including any free space after the last file
+    fn free_spans(&self) -> impl Iterator<Item = EmptyBlock> + '_ {
+        self.files
+            .iter()
+            .map(|f| (f.location, f.end_location()))
+            .chain([(self.size, self.size)])
+            .scan(0, |last_end, (location, end)| {
+                let gap = EmptyBlock {
+                    location: *last_end,
+                    length: location - *last_end,
+                };
+                *last_end = end;
+                Some(gap)
+            })
+            .filter(|gap| gap.length > 0)
+    }
+
+    fn largest_free_run(&self) -> u64 {
+        self.free_spans().map(|gap| gap.length).max().unwrap_or(0)
+    }
+
+    #[allow(dead_code)]
+    fn stats(&self) -> DiskStats {
+        let mut fragments_per_file: HashMap<u64, usize> = HashMap::new();
+        for file in self.files.iter().filter(|f| f.length > 0) {
+            (*fragments_per_file.entry(file.id).or_default()) += 1;
+        }
+        DiskStats {
+            fragments: fragments_per_file.values().sum(),
+            fragmented_files: fragments_per_file.values().filter(|&&c| c > 1).count(),
+            free_blocks: self.free_spans().map(|gap| gap.length).sum(),
+            free_spans: self.free_spans().count(),
+            largest_free_run: self.largest_free_run(),
+        }
+    }
+
+    // Puzzle notation, e.g. 00...111...2...333.44.5555.6666.777.888899. Ids above 9 only show
+    // their last digit
+    #[allow(dead_code)]
+    fn render(&self) -> String {
+        (0..self.size)
+            .map(|block| match self.file_at(block) {
+                Some(id) => char::from_digit((id % 10) as u32, 10).unwrap(),
+                None => '.',
+            })
+            .collect()
+    }
+}
+
+// Free spans bucketed by length, each bucket a min-heap on location, so the leftmost span that
+// fits a file only needs one heap top checked per length
+struct FreeSpans(Vec<BinaryHeap<Reverse<u64>>>);
+
+impl FreeSpans {
+    fn new(empty_blocks: &[EmptyBlock]) -> Self {
+        let max_length = empty_blocks.iter().map(|b| b.length).max().unwrap_or(0);
+        let mut spans = vec![BinaryHeap::new(); max_length as usize + 1];
+        for empty_block in empty_blocks {
+            if empty_block.length > 0 {
+                spans[empty_block.length as usize].push(Reverse(empty_block.location));
+            }
+        }
+        FreeSpans(spans)
+    }
+
+    // Claims the leftmost span of at least `length` that starts before `before`, returning the
+    // location claimed. Whatever is left of the span is returned to the pool
+    fn take_leftmost(&mut self, length: u64, before: u64) -> Option<u64> {
+        let (location, span_length) = self.candidates(length).min()?;
+        if location >= before {
+            return None;
+        }
+        Some(self.claim(location, span_length, length))
+    }
+
+    // Claims the shortest span of at least `length` that starts before `before`, preferring the
+    // leftmost of equally short spans
+    fn take_best_fit(&mut self, length: u64, before: u64) -> Option<u64> {
+        let (location, span_length) = self
+            .candidates(length)
+            .find(|(location, _)| *location < before)?;
+        Some(self.claim(location, span_length, length))
+    }
+
+    // The leftmost span of each length that could hold `length` blocks, shortest first
+    fn candidates(&self, length: u64) -> impl Iterator<Item = (u64, usize)> + '_ {
+        (length as usize..self.0.len())
+            .filter_map(|span_length| Some((self.0[span_length].peek()?.0, span_length)))
+    }
+
+    fn claim(&mut self, location: u64, span_length: usize, length: u64) -> u64 {
+        self.0[span_length].pop();
+        let remaining = span_length - length as usize;
+        if remaining > 0 {
+            self.0[remaining].push(Reverse(location + length));
+        }
+        location
     }
 }
 
 fn parse(input: &str) -> Disk {
-    parsers::chars(|c| c.is_numeric())
+    let (mut disk, size, _) = parsers::chars(|c| c.is_numeric())
         .map(|d| d.to_digit(10).unwrap() as u64)
         .many()
         .skip_tag("\n")
@@ -94,58 +265,126 @@ fn parse(input: &str) -> Disk {
                     (disk, end_idx + length, true)
                 }
             },
-        )
-        .0
+        );
+    disk.size = size;
+    disk
 }
 
-#[allow(dead_code)]
-pub fn part1(input: &str) -> u64 {
-    let mut disk = parse(input);
-    let mut checksum = 0;
-    let mut front_file_idx = 0_usize;
-    let mut back_file_idx = disk.files.len() - 1;
-    let mut disk_location = 0;
-    while disk_location < disk.files[back_file_idx].end_location()
-        && front_file_idx <= back_file_idx
-    {
-        if disk_location < disk.files[front_file_idx].location {
-            let empty_space = disk.files[front_file_idx].location - disk_location;
-            while disk.files[back_file_idx].length == 0 {
-                back_file_idx -= 1;
-            }
-            if disk.files[back_file_idx].location < disk_location {
-                break;
+trait CompactionStrategy {
+    fn compact(&self, disk: Disk) -> Disk;
+}
+
+// Moves single blocks from the end of the disk into the leftmost free space, splitting files
+struct BlockCompaction;
+
+impl CompactionStrategy for BlockCompaction {
+    fn compact(&self, mut disk: Disk) -> Disk {
+        let mut compacted: Vec<File> = Vec::new();
+        let mut front_file_idx = 0_usize;
+        let mut back_file_idx = disk.files.len() - 1;
+        let mut disk_location = 0;
+        while disk_location < disk.files[back_file_idx].end_location()
+            && front_file_idx <= back_file_idx
+        {
+            if disk_location < disk.files[front_file_idx].location {
+                let empty_space = disk.files[front_file_idx].location - disk_location;
+                while disk.files[back_file_idx].length == 0 {
+                    back_file_idx -= 1;
+                }
+                if disk.files[back_file_idx].location < disk_location {
+                    break;
+                }
+                let new_file = disk.files[back_file_idx].take(empty_space, disk_location);
+                disk_location += new_file.length;
+                compacted.push(new_file);
+            } else if disk_location == disk.files[front_file_idx].location {
+                disk_location += disk.files[front_file_idx].length;
+                compacted.push(disk.files[front_file_idx]);
+                front_file_idx += 1;
+            } else {
+                panic!(
+                    "invalid state, disk location is {} and front file idx is {} ",
+                    disk_location, front_file_idx
+                )
             }
-            let new_file = disk.files[back_file_idx].take(empty_space, disk_location);
-            disk_location += new_file.length;
-            checksum += new_file.checksum();
-        } else if disk_location == disk.files[front_file_idx].location {
-            disk_location += disk.files[front_file_idx].length;
-            checksum += disk.files[front_file_idx].checksum();
-            front_file_idx += 1;
-        } else {
-            panic!(
-                "invalid state, disk location is {} and front file idx is {} ",
-                disk_location, front_file_idx
-            )
         }
+        Disk::from_files(compacted, disk.size)
     }
-    checksum
 }
 
-#[allow(dead_code)]
-pub fn part2(input: &str) -> u64 {
-    let mut disk = parse(input);
+// Moves whole files, highest id first, into a free span to their left picked by `take_span`
+fn move_whole_files(
+    mut disk: Disk,
+    take_span: fn(&mut FreeSpans, u64, u64) -> Option<u64>,
+) -> Disk {
+    let mut free_spans = FreeSpans::new(&disk.empty_blocks);
     for file in disk.files.iter_mut().rev() {
-        for empty_block in disk.empty_blocks.iter_mut() {
-            if empty_block.length >= file.length && empty_block.location < file.location {
-                file.location = empty_block.location;
-                empty_block.location += file.length;
-                empty_block.length -= file.length;
-            }
+        if let Some(location) = take_span(&mut free_spans, file.length, file.location) {
+            file.location = location;
         }
     }
-    disk.files.into_iter().map(|f| f.checksum()).sum()
+    Disk::from_files(disk.files, disk.size)
+}
+
+// Moves whole files into the leftmost free span that fits them
+struct FileCompaction;
+
+impl CompactionStrategy for FileCompaction {
+    fn compact(&self, disk: Disk) -> Disk {
+        move_whole_files(disk, FreeSpans::take_leftmost)
+    }
+}
+
+// Moves whole files into the shortest free span that fits them
+#[allow(dead_code)]
+struct BestFitCompaction;
+
+impl CompactionStrategy for BestFitCompaction {
+    fn compact(&self, disk: Disk) -> Disk {
+        move_whole_files(disk, FreeSpans::take_best_fit)
+    }
+}
+
+// Moves whole files, lowest id first, into the rightmost free span that fits them, packing
+// them against the end of the disk
+#[allow(dead_code)]
+struct RightAlignCompaction;
+
+impl CompactionStrategy for RightAlignCompaction {
+    fn compact(&self, disk: Disk) -> Disk {
+        FileCompaction.compact(disk.mirrored()).mirrored()
+    }
+}
+
+// Packs every file contiguously from the start of the disk, ordered by id
+#[allow(dead_code)]
+struct Defragmentation;
+
+impl CompactionStrategy for Defragmentation {
+    fn compact(&self, disk: Disk) -> Disk {
+        let mut files = disk.files;
+        files.sort_by_key(|f| f.id);
+        let mut location = 0;
+        let files: Vec<File> = files
+            .into_iter()
+            .map(|file| {
+                let moved = File { location, ..file };
+                location += file.length;
+                moved
+            })
+            .collect();
+        Disk::from_files(files, disk.size)
+    }
+}
+
+#[allow(dead_code)]
+pub fn part1(input: &str) -> u64 {
+    BlockCompaction.compact(parse(input)).checksum()
+}
+
+#[allow(dead_code)]
+pub fn part2(input: &str) -> u64 {
+    FileCompaction.compact(parse(input)).checksum()
 }
 
 #[allow(dead_code)]
@@ -170,6 +409,141 @@ mod tests {
         assert_eq!(part2(EXAMPLE), 2858);
     }
 
+    #[test]
+    fn render_example() {
+        assert_eq!(
+            parse(EXAMPLE).render(),
+            "00...111...2...333.44.5555.6666.777.888899"
+        );
+        assert_eq!(
+            BlockCompaction.compact(parse(EXAMPLE)).render(),
+            "0099811188827773336446555566.............."
+        );
+        assert_eq!(
+            FileCompaction.compact(parse(EXAMPLE)).render(),
+            "00992111777.44.333....5555.6666.....8888.."
+        );
+    }
+
+    #[test]
+    fn inspect_example() {
+        let disk = parse(EXAMPLE);
+        assert_eq!(
+            [0, 2, 5, 40, 41].map(|block| disk.file_at(block)),
+            [Some(0), None, Some(1), Some(9), Some(9)]
+        );
+        assert_eq!(
+            disk.stats(),
+            DiskStats {
+                fragments: 10,
+                fragmented_files: 0,
+                free_blocks: 14,
+                free_spans: 8,
+                largest_free_run: 3,
+            }
+        );
+
+        let disk = BlockCompaction.compact(parse(EXAMPLE));
+        assert_eq!(
+            [2, 27, 28].map(|block| disk.file_at(block)),
+            [Some(9), Some(6), None]
+        );
+        assert_eq!(
+            disk.stats(),
+            DiskStats {
+                fragments: 13,
+                fragmented_files: 2,
+                free_blocks: 14,
+                free_spans: 1,
+                largest_free_run: 14,
+            }
+        );
+
+        assert_eq!(
+            FileCompaction.compact(parse(EXAMPLE)).stats(),
+            DiskStats {
+                fragments: 10,
+                fragmented_files: 0,
+                free_blocks: 14,
+                free_spans: 6,
+                largest_free_run: 5,
+            }
+        );
+    }
+
+    #[test]
+    fn compaction_strategies_example() {
+        let strategies: [(&dyn CompactionStrategy, &str, u64); 5] = [
+            (
+                &BlockCompaction,
+                "0099811188827773336446555566..............",
+                1928,
+            ),
+            (
+                &FileCompaction,
+                "00992111777.44.333....5555.6666.....8888..",
+                2858,
+            ),
+            (
+                &BestFitCompaction,
+                "00992111777.44.333....5555.6666.....8888..",
+                2858,
+            ),
+            (
+                &RightAlignCompaction,
+                "........111..00333.44.5555.6666.7772888899",
+                4173,
+            ),
+            (
+                &Defragmentation,
+                "0011123334455556666777888899..............",
+                2453,
+            ),
+        ];
+        for (strategy, rendered, checksum) in strategies {
+            let disk = strategy.compact(parse(EXAMPLE));
+            assert_eq!(disk.render(), rendered);
+            assert_eq!(disk.checksum(), checksum);
+        }
+    }
+
+    #[test]
+    fn compaction_strategies_generated() {
+        let disk_map = generated_disk_map(401);
+        assert_eq!(
+            BestFitCompaction.compact(parse(&disk_map)).checksum(),
+            27779824
+        );
+        assert_eq!(
+            RightAlignCompaction.compact(parse(&disk_map)).checksum(),
+            84796965
+        );
+        assert_eq!(
+            Defragmentation.compact(parse(&disk_map)).checksum(),
+            35653217
+        );
+    }
+
+    fn generated_disk_map(length: usize) -> String {
+        let mut disk_map: String = (0..length)
+            .map(|i| {
+                let digit = if i % 2 == 0 {
+                    1 + (i * i + 3 * i) % 9
+                } else {
+                    (7 * i + i * i) % 10
+                };
+                char::from_digit(digit as u32, 10).unwrap()
+            })
+            .collect();
+        disk_map.push('\n');
+        disk_map
+    }
+
+    #[test]
+    fn part2_generated() {
+        assert_eq!(part2(&generated_disk_map(4001)), 27573209466);
+    }
+
     #[test]
     fn part1_test() {
         assert_eq!(
@@ -219,4 +593,10 @@ mod tests {
             ))
         });
     }
+
+    #[bench]
+    fn part2_large_bench(b: &mut Bencher) {
+        let disk_map = generated_disk_map(2_000_001);
+        b.iter(|| part2(&disk_map));
+    }
 }
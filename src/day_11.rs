@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, ops::AddAssign};
 
 use advent_of_code::{
     itertools::Itertools,
@@ -20,34 +20,243 @@ fn parse(input: &str) -> HashMap<u64, u64> {
         .expect("Failed to parse input")
 }
 
-fn simulate_one(n: u64) -> Vec<u64> {
-    if n == 0 {
-        vec![1]
-    } else if n.ilog10() % 2 == 1 {
-        let mask = 10_u64.pow(n.ilog10() / 2 + 1);
-        vec![n / mask, n % mask]
-    } else {
-        vec![n * 2024]
+fn has_even_digits(n: u64) -> bool {
+    n > 0 && n.ilog10() % 2 == 1
+}
+
+fn split_digits(n: u64) -> Vec<u64> {
+    let mask = 10_u64.pow(n.ilog10() / 2 + 1);
+    vec![n / mask, n % mask]
+}
+
+struct Rule {
+    applies: Box<dyn Fn(u64) -> bool>,
+    rewrite: Box<dyn Fn(u64) -> Vec<u64>>,
+}
+
+// Rules are tried in order and the first one that applies rewrites the stone. Stones that no
+// rule applies to are left as they are
+struct Rules(Vec<Rule>);
+
+impl Rules {
+    fn new() -> Self {
+        Rules(Vec::new())
+    }
+
+    fn rule(
+        mut self,
+        applies: impl Fn(u64) -> bool + 'static,
+        rewrite: impl Fn(u64) -> Vec<u64> + 'static,
+    ) -> Self {
+        self.0.push(Rule {
+            applies: Box::new(applies),
+            rewrite: Box::new(rewrite),
+        });
+        self
+    }
+
+    fn simulate_one(&self, n: u64) -> Vec<u64> {
+        self.0
+            .iter()
+            .find(|rule| (rule.applies)(n))
+            .map(|rule| (rule.rewrite)(n))
+            .unwrap_or_else(|| vec![n])
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules::new()
+            .rule(|n| n == 0, |_| vec![1])
+            .rule(has_even_digits, split_digits)
+            .rule(|_| true, |n| vec![n * 2024])
     }
 }
 
-fn simulate_unordered(arrangement: HashMap<u64, u64>, depth: u64) -> HashMap<u64, u64> {
+fn simulate_unordered(
+    arrangement: HashMap<u64, u64>,
+    depth: u64,
+    rules: &Rules,
+) -> HashMap<u64, u64> {
     if depth == 0 {
         arrangement
     } else {
         let mut next_arrangement: HashMap<u64, u64> = HashMap::new();
         for (value, count) in arrangement {
-            for next_value in simulate_one(value) {
+            for next_value in rules.simulate_one(value) {
+                (*next_arrangement.entry(next_value).or_default()) += count;
+            }
+        }
+        simulate_unordered(next_arrangement, depth - 1, rules)
+    }
+}
+
+// Unsigned integer of any size, stored as base 10^9 digits with the least significant first
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct BigCount(Vec<u32>);
+
+const BIG_COUNT_BASE: u32 = 1_000_000_000;
+
+impl From<u64> for BigCount {
+    fn from(mut n: u64) -> Self {
+        let mut digits = Vec::new();
+        while n > 0 {
+            digits.push((n % BIG_COUNT_BASE as u64) as u32);
+            n /= BIG_COUNT_BASE as u64;
+        }
+        BigCount(digits)
+    }
+}
+
+impl AddAssign<&BigCount> for BigCount {
+    fn add_assign(&mut self, other: &BigCount) {
+        if self.0.len() < other.0.len() {
+            self.0.resize(other.0.len(), 0);
+        }
+        let mut carry = 0;
+        for (idx, digit) in self.0.iter_mut().enumerate() {
+            let sum = *digit + other.0.get(idx).copied().unwrap_or(0) + carry;
+            *digit = sum % BIG_COUNT_BASE;
+            carry = sum / BIG_COUNT_BASE;
+        }
+        if carry > 0 {
+            self.0.push(carry);
+        }
+    }
+}
+
+impl fmt::Display for BigCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.split_last() {
+            None => write!(f, "0"),
+            Some((most_significant, rest)) => {
+                write!(f, "{}", most_significant)?;
+                for digit in rest.iter().rev() {
+                    write!(f, "{:09}", digit)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// Total stones and number of distinct stone values after each blink, starting from no blinks
+#[allow(dead_code)]
+fn growth_curve(
+    arrangement: HashMap<u64, u64>,
+    depth: usize,
+    rules: &Rules,
+) -> Vec<(BigCount, usize)> {
+    let mut arrangement: HashMap<u64, BigCount> = arrangement
+        .into_iter()
+        .map(|(value, count)| (value, BigCount::from(count)))
+        .collect();
+    let mut curve = Vec::new();
+    for blink in 0..=depth {
+        let mut total = BigCount::default();
+        for count in arrangement.values() {
+            total += count;
+        }
+        curve.push((total, arrangement.len()));
+        if blink == depth {
+            break;
+        }
+        let mut next_arrangement: HashMap<u64, BigCount> = HashMap::new();
+        for (value, count) in arrangement.iter() {
+            for next_value in rules.simulate_one(*value) {
                 (*next_arrangement.entry(next_value).or_default()) += count;
             }
         }
-        simulate_unordered(next_arrangement, depth - 1)
+        arrangement = next_arrangement;
+    }
+    curve
+}
+
+// Memoised count(stone, depth), kept as one layer per depth over every value the stones asked
+// about so far can turn into. Counts that do not fit in a u128 are None
+#[allow(dead_code)]
+struct StoneCounter {
+    rules: Rules,
+    children: HashMap<u64, Vec<u64>>,
+    layers: Vec<HashMap<u64, Option<u128>>>,
+}
+
+impl StoneCounter {
+    #[allow(dead_code)]
+    fn new(rules: Rules) -> Self {
+        StoneCounter {
+            rules,
+            children: HashMap::new(),
+            layers: Vec::new(),
+        }
+    }
+
+    #[allow(dead_code)]
+    fn count(&mut self, stone: u64, depth: usize) -> Option<u128> {
+        self.discover(stone);
+        // while every stone turns into at least one, counts never shrink, so once a stone
+        // overflows it stays overflowed
+        let never_shrinks = self.children.values().all(|children| !children.is_empty());
+        while self.layers.len() <= depth {
+            if never_shrinks
+                && self
+                    .layers
+                    .last()
+                    .is_some_and(|layer| layer[&stone].is_none())
+            {
+                return None;
+            }
+            self.push_layer();
+        }
+        self.layers[depth][&stone]
+    }
+
+    // Adds every value reachable from stone, filling in the existing layers for them
+    fn discover(&mut self, stone: u64) {
+        let mut new_values = Vec::new();
+        let mut to_visit = vec![stone];
+        while let Some(value) = to_visit.pop() {
+            if self.children.contains_key(&value) {
+                continue;
+            }
+            let children = self.rules.simulate_one(value);
+            to_visit.extend(children.iter().copied());
+            self.children.insert(value, children);
+            new_values.push(value);
+        }
+        for depth in 0..self.layers.len() {
+            for value in new_values.iter() {
+                let count = self.layer_count(depth, *value);
+                self.layers[depth].insert(*value, count);
+            }
+        }
+    }
+
+    fn layer_count(&self, depth: usize, value: u64) -> Option<u128> {
+        if depth == 0 {
+            return Some(1);
+        }
+        self.children[&value]
+            .iter()
+            .try_fold(0_u128, |total, child| {
+                total.checked_add(self.layers[depth - 1][child]?)
+            })
+    }
+
+    fn push_layer(&mut self) {
+        let depth = self.layers.len();
+        let layer = self
+            .children
+            .keys()
+            .map(|value| (*value, self.layer_count(depth, *value)))
+            .collect();
+        self.layers.push(layer);
     }
 }
 
 #[allow(dead_code)]
 pub fn part1(input: &str) -> u64 {
-    simulate_unordered(parse(input), 25)
+    simulate_unordered(parse(input), 25, &Rules::default())
         .into_iter()
         .map(|(_, v)| v)
         .sum()
@@ -55,7 +264,7 @@ pub fn part1(input: &str) -> u64 {
 
 #[allow(dead_code)]
 pub fn part2(input: &str) -> u64 {
-    simulate_unordered(parse(input), 75)
+    simulate_unordered(parse(input), 75, &Rules::default())
         .into_iter()
         .map(|(_, v)| v)
         .sum()
@@ -83,6 +292,67 @@ mod tests {
         assert_eq!(part2(EXAMPLE), 65601038650482);
     }
 
+    #[test]
+    fn growth_curve_example() {
+        let curve = growth_curve(parse(EXAMPLE), 500, &Rules::default());
+        assert_eq!(curve[0], (BigCount::from(2), 2));
+        assert_eq!(curve[6], (BigCount::from(22), 15));
+        assert_eq!(curve[75], (BigCount::from(65601038650482), 54));
+        assert_eq!(
+            curve[500].0.to_string(),
+            "9332778333171329647192501576620127875703052322448004098317987815134809182249715590744339095"
+        );
+        assert_eq!(curve[500].1, 54);
+    }
+
+    #[test]
+    fn stone_counter_example() {
+        let mut counter = StoneCounter::new(Rules::default());
+        assert_eq!(counter.count(125, 100), Some(789123785980743959));
+        assert_eq!(
+            counter.count(0, 200),
+            Some(1128906011745822861955917175537668934)
+        );
+        assert!(counter.count(125, 213).is_some());
+        assert_eq!(counter.count(125, 214), None);
+        assert_eq!(counter.count(125, 5000), None);
+        assert_eq!(
+            counter.count(125, 75).unwrap() + counter.count(17, 75).unwrap(),
+            65601038650482
+        );
+    }
+
+    #[test]
+    fn stone_counter_vanishing_stones() {
+        // each stone below 14 turns into a thousand of the next and 14s disappear, so the count
+        // overflows after 13 blinks and drops back to nothing after 14
+        let vanishing = Rules::new()
+            .rule(|n| n < 14, |n| vec![n + 1; 1000])
+            .rule(|n| n == 14, |_| vec![]);
+        let mut counter = StoneCounter::new(vanishing);
+        assert_eq!(counter.count(1, 12), Some(10_u128.pow(36)));
+        assert_eq!(counter.count(1, 13), None);
+        assert_eq!(counter.count(1, 14), Some(0));
+        assert_eq!(counter.count(1, 20), Some(0));
+    }
+
+    #[test]
+    fn custom_rules() {
+        let halving = Rules::new()
+            .rule(|n| n == 0, |_| vec![1])
+            .rule(|n| n % 2 == 0, |n| vec![n / 2, n / 2])
+            .rule(|_| true, |n| vec![3 * n + 1]);
+        let arrangement = simulate_unordered(parse(EXAMPLE), 25, &halving);
+        assert_eq!(arrangement.values().sum::<u64>(), 163840);
+        assert_eq!(arrangement.len(), 2);
+
+        let small_only = Rules::new().rule(|n| n < 10, |n| vec![n + 1, n + 1]);
+        assert_eq!(
+            simulate_unordered(parse(EXAMPLE), 25, &small_only),
+            parse(EXAMPLE)
+        );
+    }
+
     #[test]
     fn part1_test() {
         assert_eq!(
@@ -1,9 +1,9 @@
 use advent_of_code::{
-    grid::{Direction, Grid, GridPoint},
+    grid::Grid,
     parse::{parsers, Parser},
 };
 
-use std::collections::{HashSet, VecDeque};
+use crate::regions::{Connectivity, RegionMap};
 
 fn parse(input: &str) -> Grid<char> {
     parsers::chars(|c| c.is_alphabetic())
@@ -14,97 +14,21 @@ fn parse(input: &str) -> Grid<char> {
 }
 
 #[allow(dead_code)]
-pub fn part1(input: &str) -> u32 {
-    let grid = parse(input);
-    let mut seen = HashSet::new();
-    let mut to_explore = VecDeque::from([GridPoint::new(0, 0)]);
-    let mut total = 0;
-    while let Some(seed) = to_explore.pop_front() {
-        if seen.contains(&seed) {
-            continue;
-        }
-        seen.insert(seed);
-        let mut within_region = VecDeque::from([seed]);
-        let mut seen_within_region = HashSet::new();
-        let mut perimeter = 0_u32;
-        let mut area = 0_u32;
-        while let Some(current) = within_region.pop_front() {
-            if seen_within_region.contains(&current) {
-                continue;
-            }
-            seen_within_region.insert(current);
-            area += 1;
-            for delta in Direction::all() {
-                if let Some(next) = current.add_checked(delta.into(), &grid.dimensions()) {
-                    if grid.get(next) == grid.get(current) {
-                        within_region.push_back(next);
-                    } else {
-                        perimeter += 1;
-                        to_explore.push_back(next);
-                    }
-                } else {
-                    perimeter += 1;
-                }
-            }
-        }
-        seen.extend(seen_within_region);
-        total += perimeter * area;
-    }
-    total
+pub fn part1(input: &str) -> usize {
+    RegionMap::new(&parse(input), Connectivity::Four)
+        .regions()
+        .iter()
+        .map(|region| region.area * region.perimeter)
+        .sum()
 }
 
 #[allow(dead_code)]
-pub fn part2(input: &str) -> u32 {
-    let grid = parse(input);
-    let mut seen = HashSet::new();
-    let mut to_explore = VecDeque::from([GridPoint::new(0, 0)]);
-    let mut total = 0;
-    while let Some(seed) = to_explore.pop_front() {
-        if seen.contains(&seed) {
-            continue;
-        }
-        seen.insert(seed);
-        let mut within_region = VecDeque::from([seed]);
-        let mut seen_within_region = HashSet::new();
-        let mut perimeter = 0_u32;
-        let mut area = 0_u32;
-        while let Some(current) = within_region.pop_front() {
-            if seen_within_region.contains(&current) {
-                continue;
-            }
-            seen_within_region.insert(current);
-            area += 1;
-            for delta in Direction::all() {
-                if let Some(next) = current.add_checked(delta.into(), &grid.dimensions()) {
-                    if grid.get(next) == grid.get(current) {
-                        within_region.push_back(next);
-                    } else {
-                        let right = delta.rotate_right();
-                        let right_cell = current.add_checked(right.into(), &grid.dimensions());
-                        let right_next_cell = next.add_checked(right.into(), &grid.dimensions());
-                        if !(right_cell.and_then(|c| grid.get(c).ok()) == grid.get(current).ok()
-                            && right_next_cell.and_then(|c| grid.get(c).ok())
-                                != grid.get(current).ok())
-                        {
-                            perimeter += 1;
-                        }
-                        to_explore.push_back(next);
-                    }
-                } else {
-                    if current
-                        .add_checked(delta.rotate_right().into(), &grid.dimensions())
-                        .and_then(|c| grid.get(c).ok())
-                        != grid.get(current).ok()
-                    {
-                        perimeter += 1;
-                    }
-                }
-            }
-        }
-        seen.extend(seen_within_region);
-        total += perimeter * area;
-    }
-    total
+pub fn part2(input: &str) -> usize {
+    RegionMap::new(&parse(input), Connectivity::Four)
+        .regions()
+        .iter()
+        .map(|region| region.area * region.sides)
+        .sum()
 }
 
 #[allow(dead_code)]
@@ -112,6 +36,7 @@ pub fn part2(input: &str) -> u32 {
 mod tests {
     use super::*;
     extern crate test;
+    use advent_of_code::grid::GridPoint;
     use advent_of_code::{day::Day, web_api::load_question_input};
     use test::Bencher;
 
@@ -136,6 +61,183 @@ MMMISSJEEE
     #[test]
     fn part2_example() {
         assert_eq!(part2(EXAMPLE), 1206);
+        assert_eq!(part2("AAAA\nBBCD\nBBCC\nEEEC\n"), 80);
+        assert_eq!(part2("EEEEE\nEXXXX\nEEEEE\nEXXXX\nEEEEE\n"), 236);
+        assert_eq!(
+            part2("AAAAAA\nAAABBA\nAAABBA\nABBAAA\nABBAAA\nAAAAAA\n"),
+            368
+        );
+    }
+
+    #[test]
+    fn region_stats() {
+        let map = RegionMap::new(&parse("AAAA\nBBCD\nBBCC\nEEEC\n"), Connectivity::Four);
+        let stats: Vec<(usize, usize, usize, (usize, usize), (usize, usize))> = map
+            .regions()
+            .iter()
+            .map(|region| {
+                (
+                    region.area,
+                    region.perimeter,
+                    region.sides,
+                    (region.bounds.top_left.row, region.bounds.top_left.col),
+                    (
+                        region.bounds.bottom_right.row,
+                        region.bounds.bottom_right.col,
+                    ),
+                )
+            })
+            .collect();
+        assert_eq!(
+            stats,
+            vec![
+                (4, 10, 4, (0, 0), (0, 3)),
+                (4, 8, 4, (1, 0), (2, 1)),
+                (4, 10, 8, (1, 2), (3, 3)),
+                (1, 4, 4, (1, 3), (1, 3)),
+                (3, 8, 4, (3, 0), (3, 2)),
+            ]
+        );
+        assert_eq!(map.label(GridPoint::new(3, 3)), Some(2));
+        assert_eq!(map.label(GridPoint::new(4, 0)), None);
+    }
+
+    #[test]
+    fn enclosed_regions() {
+        let map = RegionMap::new(
+            &parse("OOOOO\nOXOXO\nOOOOO\nOXOXO\nOOOOO\n"),
+            Connectivity::Four,
+        );
+        let enclosed: Vec<Option<usize>> = map
+            .regions()
+            .iter()
+            .map(|region| region.enclosed_by)
+            .collect();
+        assert_eq!(enclosed, vec![None, Some(0), Some(0), Some(0), Some(0)]);
+        assert_eq!((map.regions()[0].area, map.regions()[0].sides), (21, 20));
+
+        let map = RegionMap::new(
+            &parse("AAAAA\nABBBA\nABCBA\nABBBA\nAAAAA\n"),
+            Connectivity::Four,
+        );
+        let enclosed: Vec<Option<usize>> = map
+            .regions()
+            .iter()
+            .map(|region| region.enclosed_by)
+            .collect();
+        assert_eq!(enclosed, vec![None, Some(0), Some(1)]);
+
+        // whatever fills a hole doesn't stop the region around it being enclosed, but C and D
+        // lie side by side, so neither is surrounded by a single region
+        let map = RegionMap::new(
+            &parse("AAAAAA\nABBBBA\nABCDBA\nABBBBA\nAAAAAA\n"),
+            Connectivity::Four,
+        );
+        let enclosed: Vec<Option<usize>> = map
+            .regions()
+            .iter()
+            .map(|region| region.enclosed_by)
+            .collect();
+        assert_eq!(enclosed, vec![None, Some(0), None, None]);
+    }
+
+    #[test]
+    fn eight_connectivity() {
+        let grid = parse("AB\nBA\n");
+        assert_eq!(RegionMap::new(&grid, Connectivity::Four).regions().len(), 4);
+        let map = RegionMap::new(&grid, Connectivity::Eight);
+        let stats: Vec<(usize, usize, usize)> = map
+            .regions()
+            .iter()
+            .map(|region| (region.area, region.perimeter, region.sides))
+            .collect();
+        assert_eq!(stats, vec![(2, 8, 8), (2, 8, 8)]);
+
+        // the diagonal cells can see the surrounding A past each other, so neither is enclosed
+        let map = RegionMap::new(&parse("AAAA\nABCA\nACBA\nAAAA\n"), Connectivity::Eight);
+        let enclosed: Vec<Option<usize>> = map
+            .regions()
+            .iter()
+            .map(|region| region.enclosed_by)
+            .collect();
+        assert_eq!(enclosed, vec![None, None, None]);
+    }
+
+    fn sides(input: &str, connectivity: Connectivity) -> Vec<usize> {
+        RegionMap::new(&parse(input), connectivity)
+            .regions()
+            .iter()
+            .map(|region| region.sides)
+            .collect()
+    }
+
+    #[test]
+    fn sides_with_holes() {
+        assert_eq!(
+            sides("AAAA\nABBA\nABBA\nAAAA\n", Connectivity::Four),
+            vec![8, 4]
+        );
+        assert_eq!(
+            sides("OOOOO\nOXOXO\nOOOOO\nOXOXO\nOOOOO\n", Connectivity::Four),
+            vec![20, 4, 4, 4, 4]
+        );
+        // the hole touches the outside at a single corner without joining it
+        assert_eq!(sides("AAA\nABA\nAAB\n", Connectivity::Four), vec![10, 4, 4]);
+    }
+
+    #[test]
+    fn sides_of_nested_regions() {
+        assert_eq!(
+            sides("AAAAA\nABBBA\nABCBA\nABBBA\nAAAAA\n", Connectivity::Four),
+            vec![8, 8, 4]
+        );
+    }
+
+    #[test]
+    fn sides_when_touching_diagonally() {
+        assert_eq!(
+            sides(
+                "AAAAAA\nAAABBA\nAAABBA\nABBAAA\nABBAAA\nAAAAAA\n",
+                Connectivity::Four
+            ),
+            vec![12, 4, 4]
+        );
+        assert_eq!(sides("AB\nBA\n", Connectivity::Four), vec![4, 4, 4, 4]);
+        assert_eq!(sides("AB\nBA\n", Connectivity::Eight), vec![8, 8]);
+    }
+
+    #[test]
+    fn sides_of_single_cells() {
+        assert_eq!(sides("A\n", Connectivity::Four), vec![4]);
+        assert_eq!(sides("ABC\n", Connectivity::Four), vec![4, 4, 4]);
+        let grid = parse("A\n");
+        assert_eq!(
+            RegionMap::new(&grid, Connectivity::Four).render(&grid),
+            "+-+\n|A|\n+-+\n0 A: area 1, perimeter 4, sides 4\n"
+        );
+    }
+
+    #[test]
+    fn render_regions() {
+        let grid = parse("AAAA\nBBCD\nBBCC\nEEEC\n");
+        assert_eq!(
+            RegionMap::new(&grid, Connectivity::Four).render(&grid),
+            "+-------+
+|A A A A|
++---+-+-+
+|B B|C|D|
+|   | +-+
+|B B|C C|
++---+-+ |
+|E E E|C|
++-----+-+
+0 A: area 4, perimeter 10, sides 4
+1 B: area 4, perimeter 8, sides 4
+2 C: area 4, perimeter 10, sides 8
+3 D: area 1, perimeter 4, sides 4
+4 E: area 3, perimeter 8, sides 4
+"
+        );
     }
 
     #[test]
@@ -4,41 +4,508 @@ use advent_of_code::{
 };
 
 struct Game {
-    button_a: GridPointDelta<i64>,
-    button_b: GridPointDelta<i64>,
+    buttons: Vec<GridPointDelta<i64>>,
     target: GridPoint<i64>,
 }
 
+// Why the cheapest way to win a machine's prize can't be worked out
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum MachineError {
+    // the machine has a different number of buttons than there are prices for
+    Unpriced { buttons: usize, costs: usize },
+    // buttons that cost nothing can be pressed without running past the prize or a limit, so
+    // neither the prize nor the price bounds how often they need trying
+    Unbounded,
+    // finding the cheapest presses would take more than SEARCH_STEP_LIMIT solves
+    TooLarge,
+}
+
+const A_COST: i64 = 3;
+const B_COST: i64 = 1;
+const PART1_PRESS_LIMIT: i64 = 100;
+const SEARCH_STEP_LIMIT: u64 = 10_000_000;
+
 impl Game {
-    fn solution(&self) -> Option<(i64, i64)> {
-        let determinant = self.button_a.row_delta * self.button_b.col_delta
-            - self.button_a.col_delta * self.button_b.row_delta;
-        let a_count =
-            self.button_b.col_delta * self.target.row - self.button_b.row_delta * self.target.col;
-        let b_count =
-            self.button_a.row_delta * self.target.col - self.button_a.col_delta * self.target.row;
-        if a_count % determinant == 0 && b_count % determinant == 0 {
-            Some((a_count / determinant, b_count / determinant))
-        } else {
+    // The cheapest presses of each button that reach the prize, pressing each at most `limit`
+    // times, where costs holds the (never negative) price of each button
+    fn presses(&self, costs: &[i64], limit: Option<i64>) -> Result<Option<Vec<i64>>, MachineError> {
+        if costs.len() != self.buttons.len() {
+            return Err(MachineError::Unpriced {
+                buttons: self.buttons.len(),
+                costs: costs.len(),
+            });
+        }
+        let target = GridPointDelta::new(self.target.row, self.target.col);
+        Ok(match self.buttons[..] {
+            [button_a, button_b] => {
+                pair_presses((button_a, button_b), target, (costs[0], costs[1]), limit)
+                    .map(|(a, b)| vec![a, b])
+            }
+            [button] => {
+                let nothing = GridPointDelta::new(0, 0);
+                pair_presses((button, nothing), target, (costs[0], 0), limit)
+                    .map(|(count, _)| vec![count])
+            }
+            [] => (target == GridPointDelta::new(0, 0)).then(Vec::new),
+            _ => self.search_presses(costs, limit)?,
+        })
+    }
+
+    fn tokens(&self, costs: &[i64], limit: Option<i64>) -> Result<Option<i64>, MachineError> {
+        Ok(self.presses(costs, limit)?.map(|presses| {
+            presses
+                .iter()
+                .zip(costs)
+                .map(|(count, cost)| count * cost)
+                .sum()
+        }))
+    }
+
+    // How often a button can be pressed before overshooting the prize in a direction no button
+    // can come back from
+    fn press_bound(&self, button: usize, limit: Option<i64>) -> Option<i64> {
+        let components = |delta: GridPointDelta<i64>| [delta.row_delta, delta.col_delta];
+        let target = [self.target.row, self.target.col];
+        (0..2)
+            .filter_map(|axis| {
+                let step = components(self.buttons[button])[axis];
+                let one_way = self
+                    .buttons
+                    .iter()
+                    .all(|other| components(*other)[axis].signum() * step.signum() >= 0);
+                (step != 0 && one_way).then(|| floor_div(target[axis], step).max(0))
+            })
+            .chain(limit)
+            .min()
+    }
+
+    // Whether whole numbers of presses, negative ones included, can add up to the prize: the
+    // presses can always make up some vector u with the smallest possible X step, after which
+    // the Y steps left to fill come from what each button adds once u cancels its X step
+    fn on_lattice(&self) -> bool {
+        let (mut x_step, mut u) = (0, GridPointDelta::new(0, 0));
+        for button in &self.buttons {
+            let (divisor, x, y) = extended_gcd(x_step, button.row_delta);
+            u = GridPointDelta::new(
+                x * u.row_delta + y * button.row_delta,
+                x * u.col_delta + y * button.col_delta,
+            );
+            x_step = divisor;
+        }
+        let leftover = |delta: GridPointDelta<i64>| match x_step {
+            0 => Some(delta.col_delta),
+            _ if delta.row_delta % x_step != 0 => None,
+            _ => Some(delta.col_delta - delta.row_delta / x_step * u.col_delta),
+        };
+        let y_step = self
+            .buttons
+            .iter()
+            .filter_map(|button| leftover(*button))
+            .fold(0, gcd);
+        let target = GridPointDelta::new(self.target.row, self.target.col);
+        match (leftover(target), y_step) {
+            (None, _) => false,
+            (Some(y), 0) => y == 0,
+            (Some(y), y_step) => y % y_step == 0,
+        }
+    }
+
+    // Whether the prize lies in the directions the buttons can move in together, which fails
+    // exactly when a line through the claw has every button on one side and the prize strictly
+    // on the other. Only lines along the buttons need checking.
+    fn in_reach(&self) -> bool {
+        let target = GridPointDelta::new(self.target.row, self.target.col);
+        let dot = |first: GridPointDelta<i64>, second: GridPointDelta<i64>| {
+            first.row_delta * second.row_delta + first.col_delta * second.col_delta
+        };
+        !self.buttons.iter().any(|button| {
+            [1, -1].into_iter().any(|side| {
+                let normal = GridPointDelta::new(-button.col_delta * side, button.row_delta * side);
+                self.buttons.iter().all(|other| dot(normal, *other) >= 0) && dot(normal, target) < 0
+            })
+        })
+    }
+
+    // Enumerates presses of every button but two, leaving the pair with the most room to move
+    // to be solved exactly. The enumerated buttons are bounded by the limit, by the prize or by
+    // their price, apart from one last button that a pair spanning the plane can take care of.
+    fn search_presses(
+        &self,
+        costs: &[i64],
+        limit: Option<i64>,
+    ) -> Result<Option<Vec<i64>>, MachineError> {
+        if !self.on_lattice() || !self.in_reach() {
+            return Ok(None);
+        }
+        let mut order: Vec<(usize, Option<i64>)> = (0..self.buttons.len())
+            .map(|button| (button, self.press_bound(button, limit)))
+            .collect();
+        // unbounded buttons first, then the ones with the most presses available
+        order.sort_by_key(|(_, bound)| bound.map_or(i64::MIN, |bound| -bound));
+        let free = |(button, bound): (usize, Option<i64>)| bound.is_none() && costs[button] == 0;
+        // the pair must take every free button but one, which it can only leave when it reaches
+        // a full lattice, and should reach a full lattice if it can anyway
+        let pairs = (0..order.len()).flat_map(|i| (i + 1..order.len()).map(move |j| (i, j)));
+        let usable: Vec<(usize, usize, bool)> = pairs
+            .map(|(i, j)| {
+                let spanning = cross(self.buttons[order[i].0], self.buttons[order[j].0]) != 0;
+                (i, j, spanning)
+            })
+            .filter(|&(i, j, spanning)| {
+                let free_extras = order
+                    .iter()
+                    .enumerate()
+                    .filter(|&(k, button)| k != i && k != j && free(*button))
+                    .count();
+                free_extras == 0 || (free_extras == 1 && spanning)
+            })
+            .collect();
+        let &(i, j, spanning) = usable
+            .iter()
+            .find(|(_, _, spanning)| *spanning)
+            .or(usable.first())
+            .ok_or(MachineError::Unbounded)?;
+        let mut extras: Vec<(usize, Option<i64>)> = order
+            .iter()
+            .enumerate()
+            .filter(|(k, _)| *k != i && *k != j)
+            .map(|(_, button)| *button)
+            .collect();
+        // a free button goes last, or failing that an unbounded one, where its residues are all
+        // that need trying
+        extras.sort_by_key(|&button| (free(button), button.1.is_none()));
+        // the buttons left to enumerate that only their price can bound
+        let priced_only = extras
+            .iter()
+            .take(extras.len() - usize::from(spanning))
+            .any(|(_, bound)| bound.is_none());
+        let mut search = PressSearch {
+            game: self,
+            costs,
+            limit,
+            pair: (order[i].0, order[j].0),
+            extras,
+            presses: vec![0; self.buttons.len()],
+            best: None,
+            budget: None,
+            over_budget: false,
+            steps: 0,
+        };
+        let start = GridPointDelta::new(self.target.row, self.target.col);
+        if !priced_only {
+            search.explore(0, start, 0)?;
+            return Ok(search.best.map(|(_, presses)| presses));
+        }
+        // Every win costing at most the budget is tried, so the cheapest win found is the
+        // cheapest there is once it is within budget or the budget ruled nothing out
+        let mut budget = 1;
+        loop {
+            search.budget = Some(budget);
+            search.over_budget = false;
+            search.explore(0, start, 0)?;
+            match &search.best {
+                Some((best, _)) if *best > budget && search.over_budget => budget = *best,
+                Some(_) => return Ok(search.best.map(|(_, presses)| presses)),
+                None if search.over_budget => {
+                    budget = budget.checked_mul(2).ok_or(MachineError::TooLarge)?
+                }
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+struct PressSearch<'a> {
+    game: &'a Game,
+    costs: &'a [i64],
+    limit: Option<i64>,
+    pair: (usize, usize),
+    extras: Vec<(usize, Option<i64>)>,
+    presses: Vec<i64>,
+    best: Option<(i64, Vec<i64>)>,
+    // the most the enumerated buttons may cost between them, and whether it cut anything short
+    budget: Option<i64>,
+    over_budget: bool,
+    steps: u64,
+}
+
+impl PressSearch<'_> {
+    fn step(&mut self) -> Result<(), MachineError> {
+        self.steps += 1;
+        if self.steps > SEARCH_STEP_LIMIT {
+            return Err(MachineError::TooLarge);
+        }
+        Ok(())
+    }
+
+    fn explore(
+        &mut self,
+        depth: usize,
+        remaining: GridPointDelta<i64>,
+        spent: i64,
+    ) -> Result<(), MachineError> {
+        let Some(&(button, bound)) = self.extras.get(depth) else {
+            self.step()?;
+            let (a, b) = self.pair;
+            let buttons = (self.game.buttons[a], self.game.buttons[b]);
+            let costs = (self.costs[a], self.costs[b]);
+            let Some((a_count, b_count)) = pair_presses(buttons, remaining, costs, self.limit)
+            else {
+                return Ok(());
+            };
+            let total = spent + a_count * costs.0 + b_count * costs.1;
+            if self.best.as_ref().is_none_or(|(best, _)| total < *best) {
+                self.presses[a] = a_count;
+                self.presses[b] = b_count;
+                self.best = Some((total, self.presses.clone()));
+            }
+            return Ok(());
+        };
+        let (a, b) = self.pair;
+        if depth + 1 == self.extras.len() && cross(self.game.buttons[a], self.game.buttons[b]) != 0
+        {
+            return self.explore_residues(button, bound, remaining, spent);
+        }
+        // a button without a bound has a price, so the budget or the best so far stops it
+        let delta = self.game.buttons[button];
+        for count in 0..=bound.unwrap_or(i64::MAX) {
+            let spent = spent + count * self.costs[button];
+            if self.best.as_ref().is_some_and(|(best, _)| spent >= *best) {
+                break;
+            }
+            if self.budget.is_some_and(|budget| spent > budget) {
+                self.over_budget = true;
+                break;
+            }
+            self.presses[button] = count;
+            let remaining = GridPointDelta::new(
+                remaining.row_delta - count * delta.row_delta,
+                remaining.col_delta - count * delta.col_delta,
+            );
+            self.explore(depth + 1, remaining, spent)?;
+        }
+        self.presses[button] = 0;
+        Ok(())
+    }
+
+    // Pressing the last enumerated button |determinant| more times moves the pair's presses by
+    // whole numbers, so each residue needs one solve and the cheapest of its multiples sits at an
+    // end of the range that keeps every count in bounds, which works even without a bound
+    fn explore_residues(
+        &mut self,
+        button: usize,
+        bound: Option<i64>,
+        remaining: GridPointDelta<i64>,
+        spent: i64,
+    ) -> Result<(), MachineError> {
+        let (a, b) = self.pair;
+        let (button_a, button_b) = (self.game.buttons[a], self.game.buttons[b]);
+        let delta = self.game.buttons[button];
+        let determinant = cross(button_a, button_b);
+        let period = determinant.abs();
+        let a_shift = -cross(delta, button_b) * period / determinant;
+        let b_shift = -cross(button_a, delta) * period / determinant;
+        let last_residue = bound.map_or(period - 1, |bound| bound.min(period - 1));
+        for residue in 0..=last_residue {
+            self.step()?;
+            let target = GridPointDelta::new(
+                remaining.row_delta - residue * delta.row_delta,
+                remaining.col_delta - residue * delta.col_delta,
+            );
+            let (a_count, b_count) = (cross(target, button_b), cross(button_a, target));
+            if a_count % determinant != 0 || b_count % determinant != 0 {
+                continue;
+            }
+            let (a_count, b_count) = (a_count / determinant, b_count / determinant);
+            // the button's own count can't go negative, so there is always a lowest k
+            let Some((Some(lowest), highest)) = step_range(&[
+                (a_count, a_shift, self.limit),
+                (b_count, b_shift, self.limit),
+                (residue, period, bound),
+            ]) else {
+                continue;
+            };
+            // costs are never negative, so a cost falling with k means some count runs out
+            let slope =
+                self.costs[button] * period + self.costs[a] * a_shift + self.costs[b] * b_shift;
+            let k = match highest {
+                Some(highest) if slope <= 0 => highest,
+                _ => lowest,
+            };
+            let count = residue + period * k;
+            let (a_count, b_count) = (a_count + a_shift * k, b_count + b_shift * k);
+            let total = spent
+                + self.costs[button] * count
+                + self.costs[a] * a_count
+                + self.costs[b] * b_count;
+            if self.best.as_ref().is_none_or(|(best, _)| total < *best) {
+                self.presses[button] = count;
+                self.presses[a] = a_count;
+                self.presses[b] = b_count;
+                self.best = Some((total, self.presses.clone()));
+            }
+        }
+        self.presses[button] = 0;
+        Ok(())
+    }
+}
+
+// The cheapest presses of two buttons that reach the target, pressing each at most `limit` times
+fn pair_presses(
+    (button_a, button_b): (GridPointDelta<i64>, GridPointDelta<i64>),
+    target: GridPointDelta<i64>,
+    costs: (i64, i64),
+    limit: Option<i64>,
+) -> Option<(i64, i64)> {
+    let determinant = cross(button_a, button_b);
+    if determinant == 0 {
+        return collinear_presses((button_a, button_b), target, costs, limit);
+    }
+    let (a_count, b_count) = (cross(target, button_b), cross(button_a, target));
+    if a_count % determinant != 0 || b_count % determinant != 0 {
+        return None;
+    }
+    let (a_count, b_count) = (a_count / determinant, b_count / determinant);
+    let within_limit = |count: i64| count >= 0 && limit.is_none_or(|limit| count <= limit);
+    (within_limit(a_count) && within_limit(b_count)).then_some((a_count, b_count))
+}
+
+// Both buttons move along the same line, so the target must sit on it too and the presses
+// become a single equation with many solutions
+fn collinear_presses(
+    (button_a, button_b): (GridPointDelta<i64>, GridPointDelta<i64>),
+    target: GridPointDelta<i64>,
+    costs: (i64, i64),
+    limit: Option<i64>,
+) -> Option<(i64, i64)> {
+    let Some(direction) = [button_a, button_b, target]
+        .into_iter()
+        .find(|delta| delta.row_delta != 0 || delta.col_delta != 0)
+    else {
+        return Some((0, 0));
+    };
+    let divisor = gcd(direction.row_delta, direction.col_delta);
+    let step = GridPointDelta::new(direction.row_delta / divisor, direction.col_delta / divisor);
+    // how many steps along the line a delta is, if it lies on it
+    let steps = |delta: GridPointDelta<i64>| {
+        if delta.row_delta * step.col_delta != delta.col_delta * step.row_delta {
             None
+        } else if step.row_delta != 0 {
+            Some(delta.row_delta / step.row_delta)
+        } else {
+            Some(delta.col_delta / step.col_delta)
         }
+    };
+    cheapest_on_line(
+        steps(button_a)?,
+        steps(button_b)?,
+        steps(target)?,
+        costs,
+        limit,
+    )
+}
+
+fn cross(first: GridPointDelta<i64>, second: GridPointDelta<i64>) -> i64 {
+    first.row_delta * second.col_delta - first.col_delta * second.row_delta
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// (gcd, x, y) such that a * x + b * y == gcd
+fn extended_gcd(a: i64, b: i64) -> (i64, i64, i64) {
+    if b == 0 {
+        (a.abs(), a.signum(), 0)
+    } else {
+        let (divisor, x, y) = extended_gcd(b, a % b);
+        (divisor, y, x - (a / b) * y)
     }
 }
 
+fn floor_div(numerator: i64, denominator: i64) -> i64 {
+    numerator.div_euclid(denominator)
+}
+
+fn ceil_div(numerator: i64, denominator: i64) -> i64 {
+    -(-numerator).div_euclid(denominator)
+}
+
+// The values of k keeping every count + shift * k within 0..=upper, where a missing end is
+// unbounded, or None if there are none
+fn step_range(terms: &[(i64, i64, Option<i64>)]) -> Option<(Option<i64>, Option<i64>)> {
+    let (mut lowest, mut highest) = (None::<i64>, None::<i64>);
+    for &(count, shift, upper) in terms {
+        let (low, high) = match shift.signum() {
+            0 if count < 0 || upper.is_some_and(|upper| count > upper) => return None,
+            0 => continue,
+            1 => (
+                Some(ceil_div(-count, shift)),
+                upper.map(|upper| floor_div(upper - count, shift)),
+            ),
+            _ => (
+                upper.map(|upper| ceil_div(count - upper, -shift)),
+                Some(floor_div(count, -shift)),
+            ),
+        };
+        lowest = lowest.max(low);
+        highest = match (highest, high) {
+            (Some(highest), Some(high)) => Some(highest.min(high)),
+            (highest, high) => highest.or(high),
+        };
+    }
+    match (lowest, highest) {
+        (Some(lowest), Some(highest)) if lowest > highest => None,
+        range => Some(range),
+    }
+}
+
+// The cheapest non-negative (a, b) with a * a_steps + b * b_steps == target_steps
+fn cheapest_on_line(
+    a_steps: i64,
+    b_steps: i64,
+    target_steps: i64,
+    (a_cost, b_cost): (i64, i64),
+    limit: Option<i64>,
+) -> Option<(i64, i64)> {
+    if a_steps == 0 && b_steps == 0 {
+        return (target_steps == 0).then_some((0, 0));
+    }
+    let (divisor, x, y) = extended_gcd(a_steps, b_steps);
+    if target_steps % divisor != 0 {
+        return None;
+    }
+    // every solution is (a + a_shift * k, b + b_shift * k) for some integer k
+    let (a, b) = (x * (target_steps / divisor), y * (target_steps / divisor));
+    let (a_shift, b_shift) = (b_steps / divisor, -a_steps / divisor);
+    let (lowest, highest) = step_range(&[(a, a_shift, limit), (b, b_shift, limit)])?;
+    // the cost changes by the same amount with every step of k, so the cheapest is at an end
+    let k = if a_cost * a_shift + b_cost * b_shift > 0 {
+        lowest.or(highest)
+    } else {
+        highest.or(lowest)
+    }?;
+    Some((a + a_shift * k, b + b_shift * k))
+}
+
 fn parse(input: &str) -> Vec<Game> {
-    parsers::tag("Button A: X")
+    parsers::tag("Button ")
+        .ignore_and_then(parsers::chars(|c| c.is_ascii_uppercase()))
+        .skip_tag(": X")
         .ignore_and_then(
             parsers::signed_number()
                 .pair(", Y", parsers::signed_number())
                 .map(|(row_delta, col_delta)| GridPointDelta::new(row_delta, col_delta)),
         )
-        .skip_tag("\nButton B: X")
-        .and_then(
-            (parsers::signed_number())
-                .pair(", Y", parsers::signed_number())
-                .map(|(row_delta, col_delta)| GridPointDelta::new(row_delta, col_delta)),
-        )
-        .skip_tag("\nPrize: X=")
+        .skip_tag("\n")
+        .many()
+        .map(|buttons| buttons.collect())
+        .skip_tag("Prize: X=")
         .and_then(
             parsers::signed_number()
                 .skip_tag(", Y=")
@@ -46,11 +513,7 @@ fn parse(input: &str) -> Vec<Game> {
                 .skip_tag("\n")
                 .map(|(row, col)| GridPoint::new(row, col)),
         )
-        .map(|((button_a, button_b), target)| Game {
-            button_a,
-            button_b,
-            target,
-        })
+        .map(|(buttons, target)| Game { buttons, target })
         .list("\n")
         .parse(input)
         .finish()
@@ -58,21 +521,31 @@ fn parse(input: &str) -> Vec<Game> {
         .collect()
 }
 
+// The tokens needed to win every prize that can be won, with costs holding each button's price in
+// the order the buttons are listed
 #[allow(dead_code)]
-pub fn part1(input: &str) -> i64 {
+pub fn fewest_tokens(input: &str, costs: &[i64], limit: Option<i64>) -> Result<i64, MachineError> {
     parse(input)
         .into_iter()
-        .map(|game| game.solution().map(|(a, b)| 3 * a + b).unwrap_or(0))
+        .map(|game| Ok(game.tokens(costs, limit)?.unwrap_or(0)))
         .sum()
 }
 
+#[allow(dead_code)]
+pub fn part1(input: &str) -> i64 {
+    fewest_tokens(input, &[A_COST, B_COST], Some(PART1_PRESS_LIMIT))
+        .expect("Failed to price machines")
+}
+
 #[allow(dead_code)]
 pub fn part2(input: &str) -> i64 {
     parse(input)
         .into_iter()
         .map(|mut game| {
             game.target += GridPointDelta::new(10000000000000_i64, 10000000000000_i64);
-            game.solution().map(|(a, b)| 3 * a + b).unwrap_or(0)
+            game.tokens(&[A_COST, B_COST], None)
+                .expect("Failed to price machine")
+                .unwrap_or(0)
         })
         .sum()
 }
@@ -100,6 +573,17 @@ Prize: X=7870, Y=6450
 Button A: X+69, Y+23
 Button B: X+27, Y+71
 Prize: X=18641, Y=10279
+";
+    const EXTRA_BUTTONS: &str = "Button A: X+94, Y+34
+Button B: X+22, Y+67
+Button C: X+1, Y+1
+Prize: X=8400, Y=5400
+
+Button A: X+3, Y+0
+Button B: X+0, Y+3
+Button C: X+1, Y+1
+Button D: X+2, Y+1
+Prize: X=10, Y=10
 ";
     const DAY: Day = Day::Day13;
 
@@ -113,6 +597,276 @@ Prize: X=18641, Y=10279
         assert_eq!(part2(EXAMPLE), 875318608908);
     }
 
+    fn game(buttons: &[(i64, i64)], target: (i64, i64)) -> Game {
+        Game {
+            buttons: buttons
+                .iter()
+                .map(|(row_delta, col_delta)| GridPointDelta::new(*row_delta, *col_delta))
+                .collect(),
+            target: GridPoint::new(target.0, target.1),
+        }
+    }
+
+    #[test]
+    fn collinear_buttons() {
+        // B moves half as far for a third of the price
+        assert_eq!(
+            game(&[(2, 2), (1, 1)], (10, 10))
+                .presses(&[A_COST, B_COST], None)
+                .unwrap(),
+            Some(vec![0, 10])
+        );
+        assert_eq!(
+            game(&[(2, 2), (1, 1)], (10, 10))
+                .presses(&[A_COST, B_COST], Some(5))
+                .unwrap(),
+            Some(vec![3, 4])
+        );
+        // A moves four times as far, so it is worth pressing as often as possible
+        assert_eq!(
+            game(&[(8, 8), (2, 2)], (20, 20))
+                .presses(&[A_COST, B_COST], None)
+                .unwrap(),
+            Some(vec![2, 2])
+        );
+        // buttons pointing in opposite directions
+        assert_eq!(
+            game(&[(4, 4), (-1, -1)], (6, 6))
+                .presses(&[A_COST, B_COST], None)
+                .unwrap(),
+            Some(vec![2, 2])
+        );
+        assert_eq!(
+            game(&[(6, 4), (-9, -6)], (30, 20))
+                .presses(&[A_COST, B_COST], None)
+                .unwrap(),
+            Some(vec![5, 0])
+        );
+        // buttons that do nothing
+        assert_eq!(
+            game(&[(0, 0), (1, 1)], (5, 5))
+                .presses(&[A_COST, B_COST], None)
+                .unwrap(),
+            Some(vec![0, 5])
+        );
+        assert_eq!(
+            game(&[(0, 0), (0, 0)], (0, 0))
+                .presses(&[A_COST, B_COST], None)
+                .unwrap(),
+            Some(vec![0, 0])
+        );
+        assert_eq!(
+            game(&[(0, 0), (0, 0)], (1, 0))
+                .presses(&[A_COST, B_COST], None)
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn unreachable_collinear_prizes() {
+        // off the line both buttons move along
+        assert_eq!(
+            game(&[(1, 2), (2, 4)], (3, 5))
+                .presses(&[A_COST, B_COST], None)
+                .unwrap(),
+            None
+        );
+        // on the line, but between the points the buttons can land on
+        assert_eq!(
+            game(&[(2, 2), (4, 4)], (3, 3))
+                .presses(&[A_COST, B_COST], None)
+                .unwrap(),
+            None
+        );
+        // too far for either button to reach within the limit
+        assert_eq!(
+            game(&[(2, 2), (1, 1)], (3_000_000_000_000, 3_000_000_000_000))
+                .presses(&[A_COST, B_COST], Some(100))
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn negative_presses_rejected() {
+        assert_eq!(
+            game(&[(1, 0), (0, 1)], (-1, 2))
+                .presses(&[A_COST, B_COST], None)
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            game(&[(1, 1), (1, -1)], (0, 2))
+                .presses(&[A_COST, B_COST], None)
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            game(&[(1, 1), (1, -1)], (2, 0))
+                .presses(&[A_COST, B_COST], None)
+                .unwrap(),
+            Some(vec![1, 1])
+        );
+    }
+
+    #[test]
+    fn press_limit() {
+        assert_eq!(
+            game(&[(1, 0), (0, 1)], (101, 5))
+                .presses(&[A_COST, B_COST], None)
+                .unwrap(),
+            Some(vec![101, 5])
+        );
+        assert_eq!(
+            game(&[(1, 0), (0, 1)], (101, 5))
+                .presses(&[A_COST, B_COST], Some(PART1_PRESS_LIMIT))
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            game(&[(1, 0), (0, 1)], (100, 5))
+                .presses(&[A_COST, B_COST], Some(PART1_PRESS_LIMIT))
+                .unwrap(),
+            Some(vec![100, 5])
+        );
+        assert_eq!(
+            part1("Button A: X+1, Y+0\nButton B: X+0, Y+1\nPrize: X=101, Y=5\n"),
+            0
+        );
+        assert_eq!(
+            part1("Button A: X+1, Y+0\nButton B: X+0, Y+1\nPrize: X=100, Y=5\n"),
+            305
+        );
+    }
+
+    #[test]
+    fn extra_buttons_example() {
+        let games = parse(EXTRA_BUTTONS);
+        assert_eq!(
+            games
+                .iter()
+                .map(|game| game.buttons.len())
+                .collect::<Vec<_>>(),
+            vec![3, 4]
+        );
+        let tokens = [
+            games[0].tokens(&[3, 1, 1], Some(PART1_PRESS_LIMIT)),
+            games[1].tokens(&[3, 1, 1, 2], Some(PART1_PRESS_LIMIT)),
+        ];
+        assert_eq!(tokens, [Ok(Some(280)), Ok(Some(10))]);
+        assert_eq!(
+            games[1].presses(&[3, 3, 1, 2], None).unwrap(),
+            Some(vec![0, 0, 10, 0])
+        );
+        assert_eq!(
+            games[1].presses(&[1, 1, 5, 2], None).unwrap(),
+            Some(vec![3, 3, 1, 0])
+        );
+    }
+
+    #[test]
+    fn extra_buttons_far_away() {
+        assert_eq!(
+            game(
+                &[(94, 34), (22, 67), (1, 1)],
+                (10000000008400, 10000000005400)
+            )
+            .presses(&[3, 1, 1], None)
+            .unwrap(),
+            Some(vec![81081081161, 108108108148, 10])
+        );
+        // the extra button is never worth pressing
+        assert_eq!(
+            game(
+                &[(26, 66), (67, 21), (5, 5)],
+                (10000000012748, 10000000012176)
+            )
+            .presses(&[3, 1, 2], None)
+            .unwrap(),
+            Some(vec![118679050709, 103199174542, 0])
+        );
+    }
+
+    #[test]
+    fn extra_buttons_need_bounds() {
+        // the first two buttons undo each other, so nothing bounds them but the last button is
+        // still only worth trying up to the pair's period
+        let cancelling = game(&[(1, -1), (-1, 1), (1, 1)], (4, 2));
+        assert_eq!(
+            cancelling.presses(&[1, 1, 1], None).unwrap(),
+            Some(vec![1, 0, 3])
+        );
+        assert_eq!(
+            cancelling.presses(&[1, 1, 1], Some(10)).unwrap(),
+            Some(vec![1, 0, 3])
+        );
+        // nothing bounds any of these, but every press costs something
+        let spinning = game(&[(1, -1), (-1, 1), (1, 1), (-1, -1)], (4, 2));
+        assert_eq!(spinning.tokens(&[1, 1, 1, 1], None), Ok(Some(4)));
+        assert_eq!(spinning.tokens(&[1, 1, 1, 1], Some(10)), Ok(Some(4)));
+        // free buttons can be left to the pair and the residues, as long as only one is left over
+        assert_eq!(spinning.tokens(&[0, 1, 0, 1], None), Ok(Some(0)));
+        assert_eq!(
+            game(&[(1, -1), (-1, 1), (1, 1), (-1, -1), (2, 2)], (4, 2))
+                .presses(&[0, 0, 0, 0, 1], None),
+            Err(MachineError::Unbounded)
+        );
+    }
+
+    #[test]
+    fn unpriced_buttons() {
+        assert_eq!(
+            fewest_tokens(EXAMPLE, &[A_COST, B_COST, 1], None),
+            Err(MachineError::Unpriced {
+                buttons: 2,
+                costs: 3
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Failed to price machines")]
+    fn part1_rejects_extra_buttons() {
+        part1(EXTRA_BUTTONS);
+    }
+
+    #[test]
+    fn extra_buttons_too_many_to_try() {
+        // the first extra button could be pressed any of billions of times before the prize
+        assert_eq!(
+            game(
+                &[(94, 34), (22, 67), (1, 1), (2, 1)],
+                (10000000008400, 10000000005400)
+            )
+            .presses(&[3, 1, 1, 2], None),
+            Err(MachineError::TooLarge)
+        );
+    }
+
+    #[test]
+    fn fewer_buttons() {
+        assert_eq!(
+            game(&[(2, 0)], (10, 0)).presses(&[3], None).unwrap(),
+            Some(vec![5])
+        );
+        assert_eq!(game(&[(2, 0)], (10, 1)).presses(&[3], None).unwrap(), None);
+        assert_eq!(game(&[], (0, 0)).presses(&[], None).unwrap(), Some(vec![]));
+        assert_eq!(game(&[], (1, 0)).presses(&[], None).unwrap(), None);
+    }
+
+    #[test]
+    fn search_matches_two_button_fast_path() {
+        for game in parse(EXAMPLE) {
+            for limit in [Some(PART1_PRESS_LIMIT), None] {
+                assert_eq!(
+                    game.search_presses(&[A_COST, B_COST], limit).unwrap(),
+                    game.presses(&[A_COST, B_COST], limit).unwrap()
+                );
+            }
+        }
+    }
+
     #[test]
     fn part1_test() {
         assert_eq!(
@@ -2,8 +2,9 @@ use advent_of_code::{
     grid::{Grid, GridPoint, GridPointDelta},
     parse::{parsers, Parser},
 };
-use std::io::stdin;
-use std::{cmp::Ordering, io::BufRead};
+use std::{cmp::Ordering, fs, io, ops::Range, path::Path};
+
+use crate::regions::{Connectivity, RegionMap};
 
 struct Robot {
     position: GridPoint<i32>,
@@ -17,6 +18,30 @@ impl Robot {
         unwrapped_position.col = unwrapped_position.col.rem_euclid(width);
         unwrapped_position
     }
+
+    // How long until the robot is back where it started
+    fn period(&self, width: i32, height: i32) -> i32 {
+        lcm(
+            width / gcd(self.delta.col_delta, width),
+            height / gcd(self.delta.row_delta, height),
+        )
+    }
+
+    // When this robot and another share a cell, as the first time and how often it recurs
+    fn meeting_times(&self, other: &Robot, width: i32, height: i32) -> Option<(i32, i32)> {
+        let (col_time, col_period) = solve_congruence(
+            self.delta.col_delta - other.delta.col_delta,
+            other.position.col - self.position.col,
+            width,
+        )?;
+        let (row_time, row_period) = solve_congruence(
+            self.delta.row_delta - other.delta.row_delta,
+            other.position.row - self.position.row,
+            height,
+        )?;
+        let first = first_common_time((col_time, col_period), (row_time, row_period))?;
+        Some((first, lcm(col_period, row_period)))
+    }
 }
 
 fn parse(input: &str) -> Vec<Robot> {
@@ -40,48 +65,259 @@ fn parse(input: &str) -> Vec<Robot> {
         .collect()
 }
 
+// How many robots are in each quadrant at `time`, ignoring those on the middle row or column, in
+// the order top left, top right, bottom left, bottom right
 #[allow(dead_code)]
-pub fn part1(input: &str, width: i32, height: i32) -> i32 {
+pub fn quadrant_counts(input: &str, width: i32, height: i32, time: i32) -> [i32; 4] {
     let col_mid = width / 2;
     let row_mid = height / 2;
-    let (tl, tr, bl, br) = parse(input)
-        .into_iter()
-        .map(|robot| robot.future_position(100, width, height))
-        .fold((0, 0, 0, 0), |(tl, tr, bl, br), position| {
-            match (position.row.cmp(&row_mid), position.col.cmp(&col_mid)) {
-                (Ordering::Less, Ordering::Less) => (tl + 1, tr, bl, br),
-                (Ordering::Greater, Ordering::Less) => (tl, tr, bl + 1, br),
-                (Ordering::Less, Ordering::Greater) => (tl, tr + 1, bl, br),
-                (Ordering::Greater, Ordering::Greater) => (tl, tr, bl, br + 1),
-                _ => (tl, tr, bl, br),
-            }
-        });
-    tl * tr * bl * br
+    let mut counts = [0; 4];
+    for robot in parse(input) {
+        let position = robot.future_position(time, width, height);
+        match (position.row.cmp(&row_mid), position.col.cmp(&col_mid)) {
+            (Ordering::Less, Ordering::Less) => counts[0] += 1,
+            (Ordering::Less, Ordering::Greater) => counts[1] += 1,
+            (Ordering::Greater, Ordering::Less) => counts[2] += 1,
+            (Ordering::Greater, Ordering::Greater) => counts[3] += 1,
+            _ => {}
+        }
+    }
+    counts
+}
+
+#[allow(dead_code)]
+pub fn part1(input: &str, width: i32, height: i32) -> i32 {
+    quadrant_counts(input, width, height, 100).iter().product()
+}
+
+// How spread out a set of coordinates are, as their variance scaled by the square of their count
+fn spread(coordinates: impl Iterator<Item = i32>) -> i64 {
+    let (count, sum, sum_of_squares) = coordinates.fold((0_i64, 0_i64, 0_i64), |(n, s, q), c| {
+        (n + 1, s + c as i64, q + (c as i64) * (c as i64))
+    });
+    count * sum_of_squares - sum * sum
+}
+
+// Each axis repeats on its own period, so the time within that period when the robots bunch up
+// the most along it is where the picture sits on that axis
+fn tightest_time(
+    robots: &[Robot],
+    period: i32,
+    width: i32,
+    height: i32,
+    axis: fn(GridPoint<i32>) -> i32,
+) -> i32 {
+    (0..period)
+        .min_by_key(|time| {
+            spread(
+                robots
+                    .iter()
+                    .map(|robot| axis(robot.future_position(*time, width, height))),
+            )
+        })
+        .unwrap_or(0)
+}
+
+// The first time that is `a` modulo `a_period` and `b` modulo `b_period`, if there is one
+fn first_common_time((a, a_period): (i32, i32), (b, b_period): (i32, i32)) -> Option<i32> {
+    (0..b_period)
+        .map(|k| a + a_period * k)
+        .find(|time| time % b_period == b)
 }
 
 #[allow(dead_code)]
 pub fn part2(input: &str, width: i32, height: i32) -> i32 {
     let robots = parse(input);
-    let mut to_step = String::new();
-    let mut stdin_lock = stdin().lock();
-    let mut time = 0;
-    let mut step = 1;
-    while let Ok(_) = stdin_lock.read_line(&mut to_step) {
-        step = to_step.trim().parse::<i32>().unwrap_or(step);
-        time += step;
-        to_step = String::new();
-        let positions: Vec<GridPoint<i32>> = robots
-            .iter()
-            .map(|robot| robot.future_position(time, width, height))
-            .collect();
-        let mut grid = Grid::init(" ", height as usize, width as usize);
-        for position in positions {
-            grid.set(position.try_as_type().unwrap(), "#").unwrap();
+    let col_time = tightest_time(&robots, width, width, height, |position| position.col);
+    let row_time = tightest_time(&robots, height, width, height, |position| position.row);
+    first_common_time((col_time, width), (row_time, height))
+        .expect("Column and row periods never line up")
+}
+
+fn gcd(a: i32, b: i32) -> i32 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn lcm(a: i32, b: i32) -> i32 {
+    a / gcd(a, b) * b
+}
+
+// The times t with step * t = offset modulo `modulus`, as the first such time and how often it
+// recurs, if there are any
+fn solve_congruence(step: i32, offset: i32, modulus: i32) -> Option<(i32, i32)> {
+    let (step, offset) = (step.rem_euclid(modulus), offset.rem_euclid(modulus));
+    let divisor = gcd(step, modulus);
+    if offset % divisor != 0 {
+        return None;
+    }
+    let period = modulus / divisor;
+    let (step, offset) = (step / divisor, offset / divisor);
+    // step is invertible modulo the period, so the first time is the only one below it
+    (0..period)
+        .find(|time| (step * time - offset) % period == 0)
+        .map(|time| (time, period))
+}
+
+// How long until every robot is back where it started at the same time
+#[allow(dead_code)]
+pub fn configuration_period(input: &str, width: i32, height: i32) -> i32 {
+    parse(input)
+        .iter()
+        .map(|robot| robot.period(width, height))
+        .fold(1, lcm)
+}
+
+// Every time in the range at which two robots share a cell, with the indices of the robots in the
+// order they are listed, sorted by time
+#[allow(dead_code)]
+pub fn coincidences(
+    input: &str,
+    width: i32,
+    height: i32,
+    times: Range<i32>,
+) -> Vec<(i32, usize, usize)> {
+    let robots = parse(input);
+    let mut found = Vec::new();
+    for (first_index, first) in robots.iter().enumerate() {
+        for (second_index, second) in robots.iter().enumerate().skip(first_index + 1) {
+            let Some((time, period)) = first.meeting_times(second, width, height) else {
+                continue;
+            };
+            let skipped = ((times.start - time).max(0) + period - 1) / period;
+            let mut time = time + skipped * period;
+            while time < times.end {
+                found.push((time, first_index, second_index));
+                time += period;
+            }
+        }
+    }
+    found.sort();
+    found
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ClusterStats {
+    // groups of occupied cells joined by their edges
+    pub clusters: usize,
+    // cells in the biggest group
+    pub largest: usize,
+    // groups of a single cell
+    pub isolated: usize,
+}
+
+#[allow(dead_code)]
+pub fn cluster_stats(input: &str, width: i32, height: i32, time: i32) -> ClusterStats {
+    let mut grid = Grid::init(false, height as usize, width as usize);
+    for robot in parse(input) {
+        let position = robot.future_position(time, width, height);
+        grid.set(position.try_as_type().unwrap(), true).unwrap();
+    }
+    let sizes: Vec<usize> = RegionMap::new(&grid, Connectivity::Four)
+        .regions()
+        .iter()
+        .filter(|region| grid[region.origin])
+        .map(|region| region.area)
+        .collect();
+    ClusterStats {
+        clusters: sizes.len(),
+        largest: sizes.iter().copied().max().unwrap_or(0),
+        isolated: sizes.iter().filter(|size| **size == 1).count(),
+    }
+}
+
+const SHEET_ROBOT: [u8; 3] = [255, 255, 255];
+const SHEET_EMPTY: [u8; 3] = [0, 0, 0];
+const SHEET_GUTTER: [u8; 3] = [128, 0, 0];
+
+// Whether each cell holds at least one robot at `time`, in reading order
+fn occupancy(robots: &[Robot], time: i32, width: i32, height: i32) -> Vec<bool> {
+    let mut occupied = vec![false; (width * height) as usize];
+    for robot in robots {
+        let position = robot.future_position(time, width, height);
+        occupied[(position.row * width + position.col) as usize] = true;
+    }
+    occupied
+}
+
+// A binary greyscale image of one time, with robots in white
+fn pgm_frame(robots: &[Robot], time: i32, width: i32, height: i32) -> Vec<u8> {
+    let mut image = format!("P5\n{} {}\n255\n", width, height).into_bytes();
+    image.extend(
+        occupancy(robots, time, width, height)
+            .into_iter()
+            .map(|occupied| if occupied { 255 } else { 0 }),
+    );
+    image
+}
+
+// A binary colour image of every time in the range, laid out `columns` frames to a row with a
+// one pixel gutter around each frame
+fn ppm_sheet(
+    robots: &[Robot],
+    times: Range<i32>,
+    columns: usize,
+    width: i32,
+    height: i32,
+) -> Vec<u8> {
+    let columns = columns.max(1);
+    let (frame_width, frame_height) = (width as usize, height as usize);
+    let frames: Vec<Vec<bool>> = times
+        .map(|time| occupancy(robots, time, width, height))
+        .collect();
+    let sheet_width = columns * (frame_width + 1) + 1;
+    let sheet_height = frames.len().div_ceil(columns) * (frame_height + 1) + 1;
+    let mut pixels = vec![SHEET_GUTTER; sheet_width * sheet_height];
+    for (index, frame) in frames.iter().enumerate() {
+        let top = (index / columns) * (frame_height + 1) + 1;
+        let left = (index % columns) * (frame_width + 1) + 1;
+        for (cell, occupied) in frame.iter().enumerate() {
+            pixels[(top + cell / frame_width) * sheet_width + left + cell % frame_width] =
+                if *occupied { SHEET_ROBOT } else { SHEET_EMPTY };
         }
-        println!("{}", grid);
-        println!("{}================================================================================================================================================================================================", time);
     }
-    time
+    let mut image = format!("P6\n{} {}\n255\n", sheet_width, sheet_height).into_bytes();
+    image.extend(pixels.into_iter().flatten());
+    image
+}
+
+// Writes one PGM image per time into `directory`, named by the time they show
+#[allow(dead_code)]
+pub fn export_frames(
+    input: &str,
+    width: i32,
+    height: i32,
+    times: Range<i32>,
+    directory: &Path,
+) -> io::Result<()> {
+    let robots = parse(input);
+    fs::create_dir_all(directory)?;
+    for time in times {
+        fs::write(
+            directory.join(format!("frame_{:05}.pgm", time)),
+            pgm_frame(&robots, time, width, height),
+        )?;
+    }
+    Ok(())
+}
+
+// Writes every time in the range to a single PPM contact sheet
+#[allow(dead_code)]
+pub fn export_sheet(
+    input: &str,
+    width: i32,
+    height: i32,
+    times: Range<i32>,
+    columns: usize,
+    path: &Path,
+) -> io::Result<()> {
+    fs::write(
+        path,
+        ppm_sheet(&parse(input), times, columns, width, height),
+    )
 }
 
 #[allow(dead_code)]
@@ -107,13 +343,160 @@ p=9,5 v=-3,-3
 ";
     const DAY: Day = Day::Day14;
 
-    // Part 2 is not trivially evaluatable. Answer is 7672
+    // Robots that gather into a filled rectangle at `time`, along with some that wander on their
+    // own, wound back to where they start
+    fn hidden_picture(time: i32, width: i32, height: i32) -> String {
+        let mut state: u64 = 17;
+        let mut next = |bound: i32| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((state >> 33) % bound as u64) as i32
+        };
+        (0..500)
+            .map(|index| {
+                let (col, row) = if index < 300 {
+                    (40 + index % 15, 30 + index / 15)
+                } else {
+                    (next(width), next(height))
+                };
+                let (col_velocity, row_velocity) = (next(201) - 100, next(201) - 100);
+                format!(
+                    "p={},{} v={},{}\n",
+                    (col - col_velocity * time).rem_euclid(width),
+                    (row - row_velocity * time).rem_euclid(height),
+                    col_velocity,
+                    row_velocity
+                )
+            })
+            .collect()
+    }
 
     #[test]
     fn part1_example() {
         assert_eq!(part1(EXAMPLE, 11, 7), 12);
     }
 
+    #[test]
+    fn part2_hidden_pictures() {
+        for time in [0, 17, 4321, 10402] {
+            assert_eq!(part2(&hidden_picture(time, 101, 103), 101, 103), time);
+        }
+    }
+
+    #[test]
+    fn common_times() {
+        assert_eq!(first_common_time((3, 5), (4, 7)), Some(18));
+        assert_eq!(first_common_time((2, 4), (4, 6)), Some(10));
+        assert_eq!(first_common_time((1, 4), (2, 6)), None);
+    }
+
+    #[test]
+    fn quadrant_counts_example() {
+        assert_eq!(quadrant_counts(EXAMPLE, 11, 7, 100), [1, 3, 4, 1]);
+        assert_eq!(quadrant_counts(EXAMPLE, 11, 7, 0), [4, 0, 2, 2]);
+    }
+
+    #[test]
+    fn configuration_period_example() {
+        assert_eq!(configuration_period(EXAMPLE, 11, 7), 77);
+        let robots = parse(EXAMPLE);
+        let positions = |time| {
+            robots
+                .iter()
+                .map(|robot| robot.future_position(time, 11, 7))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(positions(0), positions(77));
+        // a robot that only moves sideways is back sooner
+        assert_eq!(configuration_period("p=0,0 v=3,0\n", 11, 7), 11);
+        assert_eq!(configuration_period("p=0,0 v=0,0\n", 11, 7), 1);
+    }
+
+    #[test]
+    fn meeting_times_example() {
+        let robots = parse("p=0,0 v=1,0\np=5,0 v=0,0\np=1,0 v=1,0\n");
+        assert_eq!(robots[0].meeting_times(&robots[1], 11, 7), Some((5, 11)));
+        assert_eq!(robots[0].meeting_times(&robots[2], 11, 7), None);
+    }
+
+    #[test]
+    fn coincidences_example() {
+        let robots = parse(EXAMPLE);
+        let mut expected = Vec::new();
+        for time in 30..200 {
+            let positions: Vec<GridPoint<i32>> = robots
+                .iter()
+                .map(|robot| robot.future_position(time, 11, 7))
+                .collect();
+            for first in 0..positions.len() {
+                for second in first + 1..positions.len() {
+                    if positions[first] == positions[second] {
+                        expected.push((time, first, second));
+                    }
+                }
+            }
+        }
+        assert_eq!(coincidences(EXAMPLE, 11, 7, 30..200), expected);
+        assert_eq!(coincidences(EXAMPLE, 11, 7, 0..1), vec![(0, 5, 7)]);
+    }
+
+    #[test]
+    fn cluster_stats_example() {
+        assert_eq!(
+            cluster_stats(EXAMPLE, 11, 7, 0),
+            ClusterStats {
+                clusters: 8,
+                largest: 2,
+                isolated: 5,
+            }
+        );
+        let picture = hidden_picture(4321, 101, 103);
+        assert!(cluster_stats(&picture, 101, 103, 4321).largest >= 300);
+        assert!(cluster_stats(&picture, 101, 103, 4320).largest < 20);
+    }
+
+    #[test]
+    fn pgm_example() {
+        let image = pgm_frame(&parse(EXAMPLE), 0, 11, 7);
+        let header = b"P5\n11 7\n255\n";
+        assert_eq!(&image[..header.len()], header);
+        let pixels = &image[header.len()..];
+        assert_eq!(pixels.len(), 77);
+        // two robots start on the same cell
+        assert_eq!(pixels.iter().filter(|pixel| **pixel == 255).count(), 11);
+        assert_eq!((pixels[0], pixels[1], pixels[4 * 11]), (255, 0, 255));
+    }
+
+    #[test]
+    fn ppm_sheet_layout() {
+        let image = ppm_sheet(&parse(EXAMPLE), 0..5, 2, 11, 7);
+        let header = b"P6\n25 25\n255\n";
+        assert_eq!(&image[..header.len()], header);
+        let pixels: Vec<[u8; 3]> = image[header.len()..]
+            .chunks(3)
+            .map(|chunk| chunk.try_into().unwrap())
+            .collect();
+        assert_eq!(pixels.len(), 25 * 25);
+        let pixel = |row: usize, col: usize| pixels[row * 25 + col];
+        assert_eq!(pixel(0, 0), SHEET_GUTTER);
+        // the robot at the top left of the first frame, and the empty cell beside it
+        assert_eq!((pixel(1, 1), pixel(1, 2)), (SHEET_ROBOT, SHEET_EMPTY));
+        // the gutter between the first two frames
+        assert_eq!(pixel(1, 12), SHEET_GUTTER);
+        // the unused slot after the fifth frame
+        assert_eq!(pixel(20, 20), SHEET_GUTTER);
+    }
+
+    #[test]
+    fn export_frames_example() {
+        let directory = std::env::temp_dir().join("day_14_export_frames_example");
+        export_frames(EXAMPLE, 11, 7, 98..101, &directory).unwrap();
+        let written = fs::read(directory.join("frame_00100.pgm")).unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(written, pgm_frame(&parse(EXAMPLE), 100, 11, 7));
+    }
+
     #[test]
     fn part1_test() {
         assert_eq!(
@@ -126,6 +509,18 @@ p=9,5 v=-3,-3
         );
     }
 
+    #[test]
+    fn part2_test() {
+        assert_eq!(
+            part2(
+                &load_question_input(crate::YEAR, crate::COOKIE_PATH, crate::INPUT_CACHE, DAY),
+                101,
+                103
+            ),
+            7672
+        );
+    }
+
     #[bench]
     fn part1_bench(b: &mut Bencher) {
         b.iter(|| {
@@ -136,4 +531,15 @@ p=9,5 v=-3,-3
             )
         });
     }
+
+    #[bench]
+    fn part2_bench(b: &mut Bencher) {
+        b.iter(|| {
+            part2(
+                &load_question_input(crate::YEAR, crate::COOKIE_PATH, crate::INPUT_CACHE, DAY),
+                101,
+                103,
+            )
+        });
+    }
 }
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use advent_of_code::{
     parse::{parsers, ParseState, Parser},
     search::{WeightedGraph, WeightedGraphWithHeuristic},
@@ -39,17 +41,207 @@ impl WeightedGraph for Towels {
     }
 }
 
+#[derive(Debug, Default)]
+struct TrieNode {
+    children: HashMap<u8, usize>,
+    terminal: bool,
+}
+
+#[derive(Debug)]
+struct TowelTrie {
+    nodes: Vec<TrieNode>,
+}
+
+impl TowelTrie {
+    fn new<'a>(towels: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut trie = TowelTrie {
+            nodes: vec![TrieNode::default()],
+        };
+        for towel in towels {
+            trie.insert(towel);
+        }
+        trie
+    }
+
+    fn insert(&mut self, towel: &str) {
+        let mut node = 0;
+        for b in towel.bytes() {
+            node = match self.nodes[node].children.get(&b) {
+                Some(&child) => child,
+                None => {
+                    self.nodes.push(TrieNode::default());
+                    let child = self.nodes.len() - 1;
+                    self.nodes[node].children.insert(b, child);
+                    child
+                }
+            };
+        }
+        self.nodes[node].terminal = true;
+    }
+
+    // Lengths of every towel that is a prefix of `design`, shortest first
+    fn prefix_lengths<'a>(&'a self, design: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        design
+            .iter()
+            .scan(0, move |node, b| {
+                *node = *self.nodes[*node].children.get(b)?;
+                Some(*node)
+            })
+            .enumerate()
+            .filter_map(|(idx, node)| {
+                if self.nodes[node].terminal {
+                    Some(idx + 1)
+                } else {
+                    None
+                }
+            })
+    }
+
+    // Returns None if the number of arrangements does not fit in a u128
+    fn arrangement_count(&self, design: &str) -> Option<u128> {
+        let design = design.as_bytes();
+        let mut counts: Vec<u128> = vec![0; design.len() + 1];
+        counts[design.len()] = 1;
+        for start in (0..design.len()).rev() {
+            counts[start] = self
+                .prefix_lengths(&design[start..])
+                .try_fold(0_u128, |total, length| {
+                    total.checked_add(counts[start + length])
+                })?;
+        }
+        Some(counts[0])
+    }
+
+    fn buildable_suffixes(&self, design: &[u8]) -> Vec<bool> {
+        let mut buildable = vec![false; design.len() + 1];
+        buildable[design.len()] = true;
+        for start in (0..design.len()).rev() {
+            buildable[start] = self
+                .prefix_lengths(&design[start..])
+                .any(|length| buildable[start + length]);
+        }
+        buildable
+    }
+
+    // Ties are broken towards the lexicographically first arrangement
+    #[allow(dead_code)]
+    fn fewest_towels<'a>(&self, design: &'a str) -> Option<Vec<&'a str>> {
+        let bytes = design.as_bytes();
+        let mut fewest: Vec<Option<(usize, usize)>> = vec![None; bytes.len() + 1];
+        fewest[bytes.len()] = Some((0, 0));
+        for start in (0..bytes.len()).rev() {
+            fewest[start] = self
+                .prefix_lengths(&bytes[start..])
+                .filter_map(|length| Some((fewest[start + length]?.0 + 1, length)))
+                .min();
+        }
+        let mut arrangement = Vec::new();
+        let mut start = 0;
+        while start < bytes.len() {
+            let (_, length) = fewest[start]?;
+            arrangement.push(&design[start..start + length]);
+            start += length;
+        }
+        Some(arrangement)
+    }
+
+    #[allow(dead_code)]
+    fn first_arrangement<'a>(&'a self, design: &'a str) -> Option<Vec<&'a str>> {
+        self.arrangements(design).next()
+    }
+
+    #[allow(dead_code)]
+    fn arrangements<'a>(&'a self, design: &'a str) -> Arrangements<'a> {
+        let buildable = self.buildable_suffixes(design.as_bytes());
+        let stack = if buildable[0] {
+            vec![(0, Vec::new())]
+        } else {
+            Vec::new()
+        };
+        Arrangements {
+            trie: self,
+            design,
+            buildable,
+            stack,
+        }
+    }
+
+    // A towel is redundant if it can be built from at least two other towels
+    fn is_redundant(&self, towel: &str) -> bool {
+        let towel = towel.as_bytes();
+        let buildable = self.buildable_suffixes(towel);
+        self.prefix_lengths(towel)
+            .any(|length| length < towel.len() && buildable[length])
+    }
+}
+
+// Yields arrangements in lexicographic order, only exploring prefixes that can be completed
+#[allow(dead_code)]
+struct Arrangements<'a> {
+    trie: &'a TowelTrie,
+    design: &'a str,
+    buildable: Vec<bool>,
+    stack: Vec<(usize, Vec<&'a str>)>,
+}
+
+impl<'a> Iterator for Arrangements<'a> {
+    type Item = Vec<&'a str>;
+
+    fn next(&mut self) -> Option<Vec<&'a str>> {
+        while let Some((start, arrangement)) = self.stack.pop() {
+            if start == self.design.len() {
+                return Some(arrangement);
+            }
+            let lengths: Vec<usize> = self
+                .trie
+                .prefix_lengths(&self.design.as_bytes()[start..])
+                .filter(|length| self.buildable[start + length])
+                .collect();
+            for length in lengths.into_iter().rev() {
+                let mut next_arrangement = arrangement.clone();
+                next_arrangement.push(&self.design[start..start + length]);
+                self.stack.push((start + length, next_arrangement));
+            }
+        }
+        None
+    }
+}
+
 #[allow(dead_code)]
 pub fn part1(input: &str) -> usize {
     let (towels, targets) = parse(input);
-    let towels = Towels(towels);
+    let towels = TowelTrie::new(towels.iter().map(|t| t.as_str()));
     targets
-        .filter_map(|t| towels.shortest_distance_with_condition(t, |s, _, _| s == "", 0))
+        .filter(|t| towels.arrangement_count(t) != Some(0))
         .count()
 }
 
 #[allow(dead_code)]
-pub fn part2(input: &str) -> u64 {
+pub fn part2(input: &str) -> u128 {
+    let (towels, targets) = parse(input);
+    let towels = TowelTrie::new(towels.iter().map(|t| t.as_str()));
+    targets
+        .map(|t| {
+            towels
+                .arrangement_count(&t)
+                .expect("Arrangement count overflowed")
+        })
+        .try_fold(0_u128, |total, count| total.checked_add(count))
+        .expect("Arrangement count overflowed")
+}
+
+#[allow(dead_code)]
+pub fn redundant_towels(input: &str) -> Vec<String> {
+    let (towels, _) = parse(input);
+    let trie = TowelTrie::new(towels.iter().map(|t| t.as_str()));
+    towels
+        .into_iter()
+        .filter(|t| trie.is_redundant(t))
+        .collect()
+}
+
+#[allow(dead_code)]
+fn part2_graph_search(input: &str) -> u64 {
     let (towels, targets) = parse(input);
     let towels = Towels(towels);
     targets
@@ -88,6 +280,47 @@ bbrgwb
         assert_eq!(part2(EXAMPLE), 16);
     }
 
+    #[test]
+    fn part2_graph_search_example() {
+        assert_eq!(part2_graph_search(EXAMPLE), 16);
+    }
+
+    #[test]
+    fn arrangement_count_overflow() {
+        let towels = TowelTrie::new(["a", "aa"]);
+        assert_eq!(
+            towels.arrangement_count("a".repeat(100).as_str()),
+            Some(573147844013817084101)
+        );
+        assert_eq!(towels.arrangement_count("a".repeat(200).as_str()), None);
+    }
+
+    #[test]
+    fn arrangements_example() {
+        let towels = TowelTrie::new(["r", "wr", "b", "g", "bwu", "rb", "gb", "br"]);
+        assert_eq!(
+            towels.arrangements("gbbr").collect::<Vec<Vec<&str>>>(),
+            vec![
+                vec!["g", "b", "b", "r"],
+                vec!["g", "b", "br"],
+                vec!["gb", "b", "r"],
+                vec!["gb", "br"],
+            ]
+        );
+        assert_eq!(towels.arrangements("ubwu").next(), None);
+        assert_eq!(towels.fewest_towels("brwrr"), Some(vec!["br", "wr", "r"]));
+        assert_eq!(
+            towels.first_arrangement("brwrr"),
+            Some(vec!["b", "r", "wr", "r"])
+        );
+        assert_eq!(towels.fewest_towels("bbrgwb"), None);
+    }
+
+    #[test]
+    fn redundant_towels_example() {
+        assert_eq!(redundant_towels(EXAMPLE), vec!["rb", "gb", "br"]);
+    }
+
     #[test]
     fn part1_test() {
         assert_eq!(
@@ -137,4 +370,16 @@ bbrgwb
             ))
         });
     }
+
+    #[bench]
+    fn part2_graph_search_bench(b: &mut Bencher) {
+        b.iter(|| {
+            part2_graph_search(&load_question_input(
+                crate::YEAR,
+                crate::COOKIE_PATH,
+                crate::INPUT_CACHE,
+                DAY,
+            ))
+        });
+    }
 }
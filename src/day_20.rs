@@ -1,4 +1,8 @@
-use std::collections::HashMap;
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    thread,
+};
 
 use advent_of_code::{
     grid::{Grid, GridPoint, GridPointDelta, Maze, MazeCell},
@@ -63,75 +67,160 @@ fn nearby_cells(distance: isize) -> impl Iterator<Item = GridPointDelta<isize>>
 }
 
 #[allow(dead_code)]
-pub fn part1(input: &str, save_count: u64) -> usize {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cheat {
+    pub start: GridPoint<usize>,
+    pub end: GridPoint<usize>,
+    pub length: u64,
+    pub saving: u64,
+}
+
+// Dense copy of the search costs so the worker threads can share them cheaply
+struct CostTable {
+    cols: usize,
+    costs: Vec<Option<u64>>,
+}
+
+impl CostTable {
+    fn new(points: &[GridPoint<usize>], cost: impl Fn(&GridPoint<usize>) -> Option<u64>) -> Self {
+        let rows = points.iter().map(|p| p.row + 1).max().unwrap_or(0);
+        let cols = points.iter().map(|p| p.col + 1).max().unwrap_or(0);
+        let mut costs = vec![None; rows * cols];
+        for point in points {
+            costs[point.row * cols + point.col] = cost(point);
+        }
+        CostTable { cols, costs }
+    }
+
+    fn get(&self, point: GridPoint<usize>) -> Option<u64> {
+        self.costs[point.row * self.cols + point.col]
+    }
+}
+
+// Every cheat of at most max_cheat_length that is no slower than the best route, including the
+// ones that save nothing such as stepping along the track. Costs are measured from both ends so
+// that branches, loops and dead ends off the best route are handled
+fn cheats(input: &str, max_cheat_length: isize) -> Vec<Cheat> {
     let (grid, special) = parse(input);
     let start_position = special.get(&Special::Start).unwrap()[0];
     let end_position = special.get(&Special::End).unwrap()[0];
-    let maze = Maze::of_grid_with_end(
-        grid,
+    let points: Vec<GridPoint<usize>> = grid.iter_points().collect();
+    let dimensions = grid.dimensions();
+    let start_maze = Maze::of_grid_with_end(
+        grid.clone(),
         |from_loc, to_loc| (to_loc.sub::<i64>(from_loc).unwrap().l1_norm() as u64),
         end_position,
     );
+    let end_maze = Maze::of_grid_with_end(
+        grid,
+        |from_loc, to_loc| (to_loc.sub::<i64>(from_loc).unwrap().l1_norm() as u64),
+        start_position,
+    );
 
-    let shortest_paths = maze
+    let from_start = start_maze
         .shortest_paths_to_many(start_position, |_, _, _| false, 0)
         .0;
-    shortest_paths
-        .shortest_path(&end_position)
+    let from_end = end_maze
+        .shortest_paths_to_many(end_position, |_, _, _| false, 0)
+        .0;
+    let Some(&best_cost) = from_start.shortest_cost(&end_position) else {
+        return Vec::new();
+    };
+    let track: Vec<(GridPoint<usize>, u64)> = points
+        .iter()
+        .filter_map(|point| Some((*point, *from_start.shortest_cost(point)?)))
+        .collect();
+    let end_costs = CostTable::new(&points, |point| from_end.shortest_cost(point).copied());
+    let offsets: Vec<(GridPointDelta<isize>, u64)> = nearby_cells(max_cheat_length)
+        .map(|delta| (delta, delta.l1_norm() as u64))
+        .collect();
+
+    let workers = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    let chunk_size = track.len().div_ceil(workers).max(1);
+    let end_costs = &end_costs;
+    let offsets = &offsets;
+    thread::scope(|scope| {
+        let handles: Vec<_> = track
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    let mut cheats = Vec::new();
+                    for &(start, start_cost) in chunk {
+                        for &(delta, length) in offsets {
+                            let Some(end) = start.add_checked(delta, &dimensions) else {
+                                continue;
+                            };
+                            match end_costs.get(end) {
+                                Some(end_cost) if start_cost + length + end_cost <= best_cost => {
+                                    cheats.push(Cheat {
+                                        start,
+                                        end,
+                                        length,
+                                        saving: best_cost - (start_cost + length + end_cost),
+                                    })
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                    cheats
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+// Maps each time saving to the number of cheats achieving it
+#[allow(dead_code)]
+pub fn cheat_savings(input: &str, max_cheat_length: isize) -> BTreeMap<u64, usize> {
+    let mut savings: BTreeMap<u64, usize> = BTreeMap::new();
+    for cheat in cheats(input, max_cheat_length)
         .into_iter()
-        .filter_map(|current| {
-            let this_cost = shortest_paths.shortest_cost(&current)?;
-            Some(
-                nearby_cells(2)
-                    .filter(|delta| {
-                        current
-                            .add_checked(*delta, &maze.dimensions())
-                            .and_then(|point| shortest_paths.shortest_cost(&point))
-                            .map(|new_cost| {
-                                *new_cost >= this_cost + (delta.l1_norm() as u64) + save_count
-                            })
-                            .unwrap_or(false)
-                    })
-                    .count(),
-            )
-        })
-        .sum()
+        .filter(|cheat| cheat.saving > 0)
+    {
+        (*savings.entry(cheat.saving).or_default()) += 1;
+    }
+    savings
 }
 
+// Every cheat that saves time, biggest savings first and then by where they start and end
 #[allow(dead_code)]
-pub fn part2(input: &str, save_count: u64) -> usize {
-    let (grid, special) = parse(input);
-    let start_position = special.get(&Special::Start).unwrap()[0];
-    let end_position = special.get(&Special::End).unwrap()[0];
-    let maze = Maze::of_grid_with_end(
-        grid,
-        |from_loc, to_loc| (to_loc.sub::<i64>(from_loc).unwrap().l1_norm() as u64),
-        end_position,
-    );
+pub fn cheat_listing(input: &str, max_cheat_length: isize) -> Vec<Cheat> {
+    let mut listing = cheats(input, max_cheat_length);
+    listing.retain(|cheat| cheat.saving > 0);
+    listing.sort_by_key(|cheat| {
+        (
+            Reverse(cheat.saving),
+            (cheat.start.row, cheat.start.col),
+            (cheat.end.row, cheat.end.col),
+        )
+    });
+    listing
+}
 
-    let shortest_paths = maze
-        .shortest_paths_to_many(start_position, |_, _, _| false, 0)
-        .0;
-    shortest_paths
-        .shortest_path(&end_position)
+// Cheats that save at least save_count, which with a save_count of zero includes the ones that
+// save nothing
+fn count_cheats(input: &str, max_cheat_length: isize, save_count: u64) -> usize {
+    cheats(input, max_cheat_length)
         .into_iter()
-        .filter_map(|current| {
-            let this_cost = shortest_paths.shortest_cost(&current)?;
-            Some(
-                nearby_cells(20)
-                    .filter(|delta| {
-                        current
-                            .add_checked(*delta, &maze.dimensions())
-                            .and_then(|point| shortest_paths.shortest_cost(&point))
-                            .map(|new_cost| {
-                                *new_cost >= this_cost + (delta.l1_norm() as u64) + save_count
-                            })
-                            .unwrap_or(false)
-                    })
-                    .count(),
-            )
-        })
-        .sum()
+        .filter(|cheat| cheat.saving >= save_count)
+        .count()
+}
+
+#[allow(dead_code)]
+pub fn part1(input: &str, save_count: u64) -> usize {
+    count_cheats(input, 2, save_count)
+}
+
+#[allow(dead_code)]
+pub fn part2(input: &str, save_count: u64) -> usize {
+    count_cheats(input, 20, save_count)
 }
 
 #[allow(dead_code)]
@@ -157,6 +246,28 @@ mod tests {
 #.#.#.#.#.#.###
 #...#...#...###
 ###############
+";
+    const BRANCHING_EXAMPLE: &str = "###########
+#S....#...#
+#.###.#.#.#
+#.#...#.#.#
+#.#.###.#.#
+#.#.....#E#
+#.#######.#
+#.........#
+###########
+";
+    const LOOPING_EXAMPLE: &str = "#########
+#S......#
+#.#####.#
+#.#...#.#
+#.#.#.#.#
+#...#...#
+###.#####
+#...#..E#
+#.###.###
+#.....#.#
+#########
 ";
     const DAY: Day = Day::Day20;
 
@@ -170,6 +281,129 @@ mod tests {
         assert_eq!(part2(EXAMPLE, 50), 285);
     }
 
+    #[test]
+    fn cheats_saving_nothing() {
+        // standing still or stepping along the track counts when nothing needs saving
+        assert_eq!(part1(EXAMPLE, 0), 296);
+        assert_eq!(part1(EXAMPLE, 1), 44);
+        assert_eq!(part2(EXAMPLE, 0), 3620);
+        assert_eq!(part2(EXAMPLE, 1), 3081);
+    }
+
+    #[test]
+    fn cheat_savings_example() {
+        assert_eq!(
+            cheat_savings(EXAMPLE, 2),
+            BTreeMap::from([
+                (2, 14),
+                (4, 14),
+                (6, 2),
+                (8, 4),
+                (10, 2),
+                (12, 3),
+                (20, 1),
+                (36, 1),
+                (38, 1),
+                (40, 1),
+                (64, 1),
+            ])
+        );
+        assert_eq!(
+            cheat_savings(EXAMPLE, 20)
+                .range(50..)
+                .map(|(saving, count)| (*saving, *count))
+                .collect::<BTreeMap<u64, usize>>(),
+            BTreeMap::from([
+                (50, 32),
+                (52, 31),
+                (54, 29),
+                (56, 39),
+                (58, 25),
+                (60, 23),
+                (62, 20),
+                (64, 19),
+                (66, 12),
+                (68, 14),
+                (70, 12),
+                (72, 22),
+                (74, 4),
+                (76, 3),
+            ])
+        );
+    }
+
+    #[test]
+    fn cheat_savings_branching() {
+        assert_eq!(
+            cheat_savings(BRANCHING_EXAMPLE, 2),
+            BTreeMap::from([(2, 1), (4, 1)])
+        );
+        assert_eq!(
+            cheat_savings(BRANCHING_EXAMPLE, 6),
+            BTreeMap::from([(2, 12), (4, 21)])
+        );
+    }
+
+    #[test]
+    fn cheat_savings_looping() {
+        assert_eq!(
+            cheat_savings(LOOPING_EXAMPLE, 2),
+            BTreeMap::from([(2, 2), (4, 3), (6, 1), (8, 2)])
+        );
+        assert_eq!(
+            cheat_savings(LOOPING_EXAMPLE, 6),
+            BTreeMap::from([(2, 29), (4, 48), (6, 15), (8, 19)])
+        );
+    }
+
+    fn serpentine_maze(corridors: usize, length: usize) -> String {
+        let mut maze = format!("{}\n", "#".repeat(length + 2));
+        for corridor in 0..corridors {
+            let turn = if corridor % 2 == 0 { length - 1 } else { 0 };
+            let mut track = vec!['.'; length];
+            if corridor == 0 {
+                track[0] = 'S';
+            }
+            if corridor + 1 == corridors {
+                track[turn] = 'E';
+            }
+            let mut separator = vec!['#'; length];
+            if corridor + 1 < corridors {
+                separator[turn] = '.';
+            }
+            maze += &format!("#{}#\n", track.into_iter().collect::<String>());
+            maze += &format!("#{}#\n", separator.into_iter().collect::<String>());
+        }
+        maze
+    }
+
+    #[test]
+    fn cheat_savings_large_radius() {
+        let savings = cheat_savings(&serpentine_maze(10, 30), 50);
+        assert_eq!(savings.values().sum::<usize>(), 42133);
+        assert_eq!(savings.range(100..).map(|(_, c)| c).sum::<usize>(), 19104);
+        assert_eq!(savings.range(200..).map(|(_, c)| c).sum::<usize>(), 3514);
+    }
+
+    #[test]
+    fn cheats_example() {
+        let listing = cheat_listing(EXAMPLE, 2);
+        assert_eq!(listing.len(), 44);
+        assert_eq!(
+            listing[0],
+            Cheat {
+                start: GridPoint::new(7, 7),
+                end: GridPoint::new(7, 5),
+                length: 2,
+                saving: 64,
+            }
+        );
+        assert_eq!(listing[1].saving, 40);
+        assert!(listing
+            .windows(2)
+            .all(|pair| pair[0].saving >= pair[1].saving));
+    }
+
     #[test]
     fn part1_test() {
         assert_eq!(
@@ -211,4 +445,10 @@ mod tests {
             )
         });
     }
+
+    #[bench]
+    fn large_radius_bench(b: &mut Bencher) {
+        let maze = serpentine_maze(100, 200);
+        b.iter(|| cheat_savings(&maze, 100));
+    }
 }
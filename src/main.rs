@@ -25,6 +25,7 @@ mod day_18;
 mod day_19;
 mod day_20;
 mod day_21;
+mod regions;
 
 #[allow(dead_code)]
 const YEAR: &str = "2024";
@@ -0,0 +1,289 @@
+use advent_of_code::grid::{Grid, GridPoint};
+
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt::Display,
+};
+
+// (row, col) offsets for north, east, south and west, so the next entry is always a right turn
+const EDGE_DELTAS: [(isize, isize); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+// north-east, south-east, south-west and north-west, each between two consecutive edges
+const DIAGONAL_DELTAS: [(isize, isize); 4] = [(-1, 1), (1, 1), (1, -1), (-1, -1)];
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    // cells sharing an edge
+    Four,
+    // cells sharing an edge or a corner
+    Eight,
+}
+
+impl Connectivity {
+    fn deltas(self) -> impl Iterator<Item = (isize, isize)> {
+        let diagonals = match self {
+            Connectivity::Four => 0,
+            Connectivity::Eight => DIAGONAL_DELTAS.len(),
+        };
+        EDGE_DELTAS
+            .into_iter()
+            .chain(DIAGONAL_DELTAS.into_iter().take(diagonals))
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoundingBox {
+    pub top_left: GridPoint<usize>,
+    pub bottom_right: GridPoint<usize>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub label: usize,
+    // the first cell of the region in reading order
+    pub origin: GridPoint<usize>,
+    pub area: usize,
+    // number of unit fence segments between the region and anything else
+    pub perimeter: usize,
+    // number of straight fence runs
+    pub sides: usize,
+    pub bounds: BoundingBox,
+    // the single region every cell touching the region from outside belongs to, if the region
+    // is away from the edge; cells in holes inside the region don't count
+    pub enclosed_by: Option<usize>,
+}
+
+pub struct RegionMap {
+    rows: usize,
+    cols: usize,
+    labels: Vec<usize>,
+    regions: Vec<Region>,
+}
+
+impl RegionMap {
+    // Labels every connected component of equal cells, numbering them in reading order
+    pub fn new<T: PartialEq>(grid: &Grid<T>, connectivity: Connectivity) -> Self {
+        let rows = grid.iter_points().map(|p| p.row + 1).max().unwrap_or(0);
+        let cols = grid.iter_points().map(|p| p.col + 1).max().unwrap_or(0);
+        let mut map = RegionMap {
+            rows,
+            cols,
+            labels: vec![usize::MAX; rows * cols],
+            regions: Vec::new(),
+        };
+        let cell = |idx: usize| &grid[GridPoint::new(idx / cols, idx % cols)];
+        let mut members = Vec::new();
+        for seed in 0..rows * cols {
+            if map.labels[seed] != usize::MAX {
+                continue;
+            }
+            let label = map.regions.len();
+            map.labels[seed] = label;
+            members.clear();
+            let mut to_explore = VecDeque::from([seed]);
+            while let Some(current) = to_explore.pop_front() {
+                members.push(current);
+                for delta in connectivity.deltas() {
+                    let Some(next) = map.offset(current, delta) else {
+                        continue;
+                    };
+                    if map.labels[next] == usize::MAX && cell(next) == cell(seed) {
+                        map.labels[next] = label;
+                        to_explore.push_back(next);
+                    }
+                }
+            }
+            let region = map.measure(label, &members);
+            map.regions.push(region);
+        }
+        let enclosers = map.enclosers(connectivity);
+        for (region, enclosed_by) in map.regions.iter_mut().zip(enclosers) {
+            region.enclosed_by = enclosed_by;
+        }
+        map
+    }
+
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    #[allow(dead_code)]
+    pub fn label(&self, point: GridPoint<usize>) -> Option<usize> {
+        if point.row < self.rows && point.col < self.cols {
+            Some(self.labels[point.row * self.cols + point.col])
+        } else {
+            None
+        }
+    }
+
+    // Draws the fences between regions around the grid's cells, with a `+` wherever a fence turns
+    // or meets another, followed by a line per region
+    #[allow(dead_code)]
+    pub fn render<T: Display>(&self, grid: &Grid<T>) -> String {
+        let label_at = |row: Option<usize>, col: Option<usize>| match (row, col) {
+            (Some(row), Some(col)) if row < self.rows && col < self.cols => {
+                Some(self.labels[row * self.cols + col])
+            }
+            _ => None,
+        };
+        let mut output = String::new();
+        for row in 0..=self.rows * 2 {
+            for col in 0..=self.cols * 2 {
+                let (cell_row, cell_col) = (row / 2, col / 2);
+                let (above, left) = (cell_row.checked_sub(1), cell_col.checked_sub(1));
+                // the labels of the cells around this position, any of which may be off the grid
+                let north_west = label_at(above, left);
+                let north_east = label_at(above, Some(cell_col));
+                let south_west = label_at(Some(cell_row), left);
+                let south_east = label_at(Some(cell_row), Some(cell_col));
+                let symbol = match (row % 2, col % 2) {
+                    (1, 1) => {
+                        output.push_str(&grid[GridPoint::new(cell_row, cell_col)].to_string());
+                        continue;
+                    }
+                    (0, 1) if north_east != south_east => '-',
+                    (1, 0) if south_west != south_east => '|',
+                    (0, 0) => {
+                        let vertical = north_west != north_east || south_west != south_east;
+                        let horizontal = north_west != south_west || north_east != south_east;
+                        match (vertical, horizontal) {
+                            (true, true) => '+',
+                            (true, false) => '|',
+                            (false, true) => '-',
+                            (false, false) => ' ',
+                        }
+                    }
+                    _ => ' ',
+                };
+                output.push(symbol);
+            }
+            output.push('\n');
+        }
+        for region in &self.regions {
+            output.push_str(&format!(
+                "{} {}: area {}, perimeter {}, sides {}\n",
+                region.label, grid[region.origin], region.area, region.perimeter, region.sides
+            ));
+        }
+        output
+    }
+
+    fn offset(&self, idx: usize, (row_delta, col_delta): (isize, isize)) -> Option<usize> {
+        let row = (idx / self.cols).checked_add_signed(row_delta)?;
+        let col = (idx % self.cols).checked_add_signed(col_delta)?;
+        if row < self.rows && col < self.cols {
+            Some(row * self.cols + col)
+        } else {
+            None
+        }
+    }
+
+    fn is_member(&self, idx: Option<usize>, label: usize) -> bool {
+        idx.is_some_and(|idx| self.labels[idx] == label)
+    }
+
+    fn measure(&self, label: usize, members: &[usize]) -> Region {
+        let mut perimeter = 0;
+        let mut sides = 0;
+        for &idx in members {
+            let edges = EDGE_DELTAS.map(|delta| self.is_member(self.offset(idx, delta), label));
+            perimeter += edges.iter().filter(|inside| !**inside).count();
+            // a closed fence turns once per side, so count the corners at each of the cell's four
+            // vertices: either both edges are open, or both are closed with a gap between them
+            for (heading, diagonal) in DIAGONAL_DELTAS.into_iter().enumerate() {
+                let (ahead, right) = (edges[heading], edges[(heading + 1) % 4]);
+                let convex = !ahead && !right;
+                let concave = ahead && right && !self.is_member(self.offset(idx, diagonal), label);
+                if convex || concave {
+                    sides += 1;
+                }
+            }
+        }
+        let rows = members.iter().map(|idx| idx / self.cols);
+        let cols = members.iter().map(|idx| idx % self.cols);
+        Region {
+            label,
+            origin: GridPoint::new(members[0] / self.cols, members[0] % self.cols),
+            area: members.len(),
+            perimeter,
+            sides,
+            bounds: BoundingBox {
+                top_left: GridPoint::new(rows.clone().min().unwrap(), cols.clone().min().unwrap()),
+                bottom_right: GridPoint::new(rows.max().unwrap(), cols.max().unwrap()),
+            },
+            enclosed_by: None,
+        }
+    }
+
+    // The region on the far side of every fence on the outside of each region. Holes inside a
+    // region don't count, so everything the outside can reach without crossing the region has to
+    // be found first.
+    fn enclosers(&self, connectivity: Connectivity) -> Vec<Option<usize>> {
+        // the outside seeps through any gap the region's own connectivity doesn't close
+        let seeps = match connectivity {
+            Connectivity::Four => Connectivity::Eight,
+            Connectivity::Eight => Connectivity::Four,
+        };
+        self.regions
+            .iter()
+            .map(|region| self.encloser(region, connectivity, seeps))
+            .collect()
+    }
+
+    fn encloser(
+        &self,
+        region: &Region,
+        connectivity: Connectivity,
+        seeps: Connectivity,
+    ) -> Option<usize> {
+        let BoundingBox {
+            top_left,
+            bottom_right,
+        } = region.bounds;
+        // touching the edge of the grid means nothing can surround it
+        if top_left.row == 0
+            || top_left.col == 0
+            || bottom_right.row + 1 == self.rows
+            || bottom_right.col + 1 == self.cols
+        {
+            return None;
+        }
+        // everything beyond the ring of cells just outside the bounding box is outside too
+        let (top, left) = (top_left.row - 1, top_left.col - 1);
+        let (bottom, right) = (bottom_right.row + 1, bottom_right.col + 1);
+        let in_box = |idx: usize| {
+            (top..=bottom).contains(&(idx / self.cols))
+                && (left..=right).contains(&(idx % self.cols))
+        };
+        let mut outside: HashSet<usize> = (top..=bottom)
+            .flat_map(|row| (left..=right).map(move |col| (row, col)))
+            .filter(|&(row, col)| row == top || row == bottom || col == left || col == right)
+            .map(|(row, col)| row * self.cols + col)
+            .collect();
+        let mut to_explore: VecDeque<usize> = outside.iter().copied().collect();
+        while let Some(current) = to_explore.pop_front() {
+            for delta in seeps.deltas() {
+                let Some(next) = self.offset(current, delta) else {
+                    continue;
+                };
+                if in_box(next) && self.labels[next] != region.label && outside.insert(next) {
+                    to_explore.push_back(next);
+                }
+            }
+        }
+        let mut surrounding = outside
+            .into_iter()
+            .filter(|&idx| {
+                connectivity
+                    .deltas()
+                    .any(|delta| self.is_member(self.offset(idx, delta), region.label))
+            })
+            .map(|idx| self.labels[idx]);
+        let encloser = surrounding.next()?;
+        surrounding
+            .all(|label| label == encloser)
+            .then_some(encloser)
+    }
+}